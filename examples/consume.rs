@@ -5,7 +5,7 @@ use std::sync::Arc;
 use crate::shared::TestModel;
 use grapple_kafka::{
     async_trait::async_trait,
-    consumer::{ConsumerConfig, StateReceiver},
+    consumer::{CommitStrategy, ConsumerConfig, StateReceiver, Subscription},
     decode,
     service::KafkaService,
     Error, Result,
@@ -21,10 +21,16 @@ async fn main() -> Result<()> {
     // -- Create consumer
     let consumer_config = ConsumerConfig {
         group_id: "test-group".to_string(),
-        topics: vec!["test-topic".to_string()],
+        subscription: Subscription::Topics(vec!["test-topic".to_string()]),
         uri: "localhost:9094".to_string(),
         offset_reset: "earliest".to_string(),
-        commit_mode: CommitMode::Async,
+        commit_strategy: CommitStrategy::PerMessage(CommitMode::Async),
+        start_position: None,
+        max_in_flight: None,
+        assignment_strategy: None,
+        rate_limit: None,
+        handler_timeout: None,
+        read_committed: true,
     };
 
     let state = Arc::new(MyState);
@@ -20,7 +20,7 @@ async fn main() -> Result<()> {
     let key = "test-key".to_string();
     let payload = "test".to_string();
 
-    match kafka_service.produce(&topic, &(&key, &payload)).await {
+    match kafka_service.produce(topic, &(&key, &payload)).await {
         Ok(()) => println!("✅ Successfully produced tuple message"),
         Err(e) => eprintln!("❌ Failed to produce tuple: {}", e),
     }
@@ -31,7 +31,7 @@ async fn main() -> Result<()> {
         data2: "test2".to_string(),
     };
 
-    match kafka_service.produce(&topic, &model).await {
+    match kafka_service.produce(topic, &model).await {
         Ok(()) => println!("✅ Successfully produced model message"),
         Err(e) => eprintln!("❌ Failed to produce model: {}", e),
     }
@@ -48,8 +48,9 @@ async fn main() -> Result<()> {
         },
     ];
 
-    for msg in messages {
-        kafka_service.produce(&topic, &msg).await?;
+    let results = kafka_service.produce_many(topic, &messages).await;
+    for (msg, result) in messages.iter().zip(results) {
+        result?;
         println!("✅ Produced: {:?}", msg);
     }
 
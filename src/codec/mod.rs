@@ -21,6 +21,28 @@ pub fn decode<D: Decode<()>>(data: &[u8]) -> Result<D> {
     Ok(decoded)
 }
 
+/// UTF-8 bytes of an encoded `String` without decoding it, `None` when the
+/// length prefix is malformed or longer than the data
+pub(crate) fn encoded_str(data: &[u8]) -> Option<&[u8]> {
+    // Variable length integer of the standard config, little endian
+    let (&first, rest) = data.split_first()?;
+    let (len, rest) = match first {
+        0..=250 => (first as u64, rest),
+        251 => rest
+            .split_first_chunk()
+            .map(|(len, rest)| (u16::from_le_bytes(*len) as u64, rest))?,
+        252 => rest
+            .split_first_chunk()
+            .map(|(len, rest)| (u32::from_le_bytes(*len) as u64, rest))?,
+        253 => rest
+            .split_first_chunk()
+            .map(|(len, rest)| (u64::from_le_bytes(*len), rest))?,
+        _ => return None,
+    };
+
+    rest.get(..usize::try_from(len).ok()?)
+}
+
 // region:    --- Tests
 
 #[cfg(test)]
@@ -68,6 +90,19 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_encoded_str() -> Result<()> {
+        for len in [0, 3, 250, 251, 300, 70_000] {
+            let data = "a".repeat(len);
+            let encoded = encode(&data)?;
+
+            assert_eq!(encoded_str(&encoded), Some(data.as_bytes()));
+            assert_eq!(encoded_str(&encoded[..encoded.len() - 1]), None);
+        }
+
+        Ok(())
+    }
 }
 
 // endregion: --- Tests
@@ -0,0 +1,90 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use tokio::sync::Notify;
+
+/// Counts messages handed to handlers but not acknowledged yet.
+#[derive(Clone)]
+pub(crate) struct InFlight {
+    inner: Arc<InFlightInner>,
+}
+
+struct InFlightInner {
+    count: AtomicUsize,
+    max: usize,
+    notify: Notify,
+}
+
+impl InFlight {
+    pub(crate) fn new(max: usize) -> Self {
+        Self {
+            inner: Arc::new(InFlightInner {
+                count: AtomicUsize::new(0),
+                max,
+                notify: Notify::new(),
+            }),
+        }
+    }
+
+    pub(crate) fn acquire(&self) -> InFlightGuard {
+        self.inner.count.fetch_add(1, Ordering::AcqRel);
+        InFlightGuard(self.clone())
+    }
+
+    pub(crate) fn is_full(&self) -> bool {
+        self.inner.count.load(Ordering::Acquire) >= self.inner.max
+    }
+
+    /// Resolves once the number of in-flight messages drops below the bound
+    pub(crate) async fn below_bound(&self) {
+        loop {
+            let notified = self.inner.notify.notified();
+            if !self.is_full() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+pub(crate) struct InFlightGuard(InFlight);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.0.inner.count.fetch_sub(1, Ordering::AcqRel);
+        self.0.inner.notify.notify_waiters();
+    }
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_in_flight_bound() {
+        let in_flight = InFlight::new(2);
+
+        let first = in_flight.acquire();
+        assert!(!in_flight.is_full());
+
+        let second = in_flight.acquire();
+        assert!(in_flight.is_full());
+
+        let waiter = tokio::spawn({
+            let in_flight = in_flight.clone();
+            async move { in_flight.below_bound().await }
+        });
+
+        drop(first);
+        waiter.await.unwrap();
+        assert!(!in_flight.is_full());
+
+        drop(second);
+    }
+}
+
+// endregion: --- Tests
@@ -0,0 +1,268 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use rdkafka::{
+    consumer::{CommitMode, Consumer},
+    Offset, TopicPartitionList,
+};
+
+use super::{backpressure::InFlightGuard, InnerConsumer};
+use crate::Result;
+
+/// When and how offsets of processed messages are committed.
+#[derive(Debug, Clone, Copy)]
+pub enum CommitStrategy {
+    /// Commit every message right after it was processed successfully.
+    PerMessage(CommitMode),
+    /// Store the offset after a successful `process` and let librdkafka
+    /// auto-commit stored offsets (`enable.auto.offset.store=false`).
+    AutoStore,
+    /// Store the offset after a successful `process` and commit stored
+    /// offsets in one batch every `interval`.
+    Periodic {
+        interval: Duration,
+        mode: CommitMode,
+    },
+    /// Nothing is committed by the consumer, the handler commits through
+    /// the [`Acknowledgement`] it receives.
+    Manual(CommitMode),
+}
+
+impl Default for CommitStrategy {
+    fn default() -> Self {
+        Self::PerMessage(CommitMode::Async)
+    }
+}
+
+impl CommitStrategy {
+    pub(crate) fn apply(&self, config: &mut rdkafka::ClientConfig) {
+        let (auto_commit, auto_store) = match self {
+            Self::PerMessage(_) => ("false", "true"),
+            Self::AutoStore => ("true", "false"),
+            Self::Periodic { .. } => ("false", "false"),
+            Self::Manual(_) => ("false", "true"),
+        };
+
+        config
+            .set("enable.auto.commit", auto_commit)
+            .set("enable.auto.offset.store", auto_store);
+    }
+
+    /// What an [`Acknowledgement`] does when the consumer does not commit
+    /// itself (streams): store the offset for auto-commit, or commit it
+    pub(crate) fn ack_mode(&self) -> AckMode {
+        match *self {
+            Self::AutoStore => AckMode::Store,
+            Self::PerMessage(mode) | Self::Periodic { mode, .. } | Self::Manual(mode) => {
+                AckMode::Commit(mode)
+            }
+        }
+    }
+
+    pub(crate) fn with_mode(self, mode: CommitMode) -> Self {
+        match self {
+            Self::PerMessage(_) => Self::PerMessage(mode),
+            Self::AutoStore => Self::AutoStore,
+            Self::Periodic { interval, .. } => Self::Periodic { interval, mode },
+            Self::Manual(_) => Self::Manual(mode),
+        }
+    }
+}
+
+// region:    --- Acknowledgement
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum AckMode {
+    Commit(CommitMode),
+    /// Store the offset for the next auto-commit
+    Store,
+}
+
+/// Handle used to commit a message once it is really done with, e.g. after an
+/// async DB transaction completes. Acknowledgements may complete in any order,
+/// the committed offset of a partition only advances past messages whose
+/// acknowledgement completed. Dropping one without committing gives up on its
+/// message, later acknowledgements commit past it.
+pub struct Acknowledgement {
+    consumer: Arc<InnerConsumer>,
+    topic: String,
+    partition: i32,
+    offset: i64,
+    mode: AckMode,
+    completed: bool,
+    _in_flight: Option<InFlightGuard>,
+}
+
+impl Acknowledgement {
+    pub(crate) fn new(
+        consumer: Arc<InnerConsumer>,
+        topic: &str,
+        partition: i32,
+        offset: i64,
+        mode: AckMode,
+        in_flight: Option<InFlightGuard>,
+    ) -> Self {
+        consumer.context().acks.register(topic, partition, offset);
+
+        Self {
+            consumer,
+            topic: topic.to_string(),
+            partition,
+            offset,
+            mode,
+            completed: false,
+            _in_flight: in_flight,
+        }
+    }
+
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    pub fn partition(&self) -> i32 {
+        self.partition
+    }
+
+    pub fn offset(&self) -> i64 {
+        self.offset
+    }
+
+    pub fn commit(mut self) -> Result<()> {
+        self.completed = true;
+        self.complete(true)
+    }
+
+    fn complete(&self, acked: bool) -> Result<()> {
+        let acks = &self.consumer.context().acks;
+        acks.complete(&self.topic, self.partition, self.offset, acked, |next| {
+            let mut tpl = TopicPartitionList::new();
+            tpl.add_partition_offset(&self.topic, self.partition, Offset::Offset(next))?;
+            match self.mode {
+                AckMode::Commit(mode) => self.consumer.commit(&tpl, mode)?,
+                AckMode::Store => self.consumer.store_offsets(&tpl)?,
+            }
+
+            Ok(())
+        })
+    }
+}
+
+impl Drop for Acknowledgement {
+    fn drop(&mut self) {
+        if !self.completed {
+            if let Err(e) = self.complete(false) {
+                tracing::error!("Commit error: {}", e);
+            }
+        }
+    }
+}
+
+/// Outstanding acknowledgements of every partition, so acknowledgements
+/// completing out of order never commit past a message still being worked on
+/// nor move the committed offset backwards.
+#[derive(Default)]
+pub(crate) struct AckTracker {
+    partitions: Mutex<HashMap<(String, i32), PartitionAcks>>,
+}
+
+#[derive(Default)]
+struct PartitionAcks {
+    pending: BTreeSet<i64>,
+    /// Acknowledged offsets not covered by a commit yet
+    acked: BTreeSet<i64>,
+    /// Next offset to consume, as last committed
+    committed: Option<i64>,
+}
+
+impl AckTracker {
+    pub(crate) fn register(&self, topic: &str, partition: i32, offset: i64) {
+        self.partitions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry((topic.to_string(), partition))
+            .or_default()
+            .pending
+            .insert(offset);
+    }
+
+    /// Marks the offset acknowledged (or given up on) and calls `commit` with
+    /// the next offset to consume when the completed prefix of the partition
+    /// grew. Runs under the lock so commits are issued in order.
+    pub(crate) fn complete(
+        &self,
+        topic: &str,
+        partition: i32,
+        offset: i64,
+        acked: bool,
+        commit: impl FnOnce(i64) -> Result<()>,
+    ) -> Result<()> {
+        let mut partitions = self.partitions.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(acks) = partitions.get_mut(&(topic.to_string(), partition)) else {
+            return Ok(());
+        };
+
+        acks.pending.remove(&offset);
+        if acked {
+            acks.acked.insert(offset);
+        }
+
+        let done_below = acks.pending.first().copied().unwrap_or(i64::MAX);
+        let Some(next) = acks.acked.range(..done_below).next_back().map(|o| o + 1) else {
+            return Ok(());
+        };
+
+        if acks.committed.is_none_or(|committed| next > committed) {
+            commit(next)?;
+            acks.committed = Some(next);
+        }
+        acks.acked = acks.acked.split_off(&next);
+
+        Ok(())
+    }
+}
+
+// endregion: --- Acknowledgement
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn complete(tracker: &AckTracker, offset: i64, acked: bool) -> Option<i64> {
+        let mut committed = None;
+        tracker
+            .complete("topic", 0, offset, acked, |next| {
+                committed = Some(next);
+                Ok(())
+            })
+            .unwrap();
+        committed
+    }
+
+    #[test]
+    fn test_ack_tracker_out_of_order() {
+        let tracker = AckTracker::default();
+        for offset in 10..=15 {
+            tracker.register("topic", 0, offset);
+        }
+
+        // 11 is still pending, nothing past it may be committed
+        assert_eq!(complete(&tracker, 15, true), None);
+        assert_eq!(complete(&tracker, 10, true), Some(11));
+        assert_eq!(complete(&tracker, 12, true), None);
+        // Given up on, later acknowledgements commit past it
+        assert_eq!(complete(&tracker, 13, false), None);
+        assert_eq!(complete(&tracker, 11, true), Some(13));
+        assert_eq!(complete(&tracker, 14, true), Some(16));
+
+        // Redelivered after a rewind, never commits backwards
+        tracker.register("topic", 0, 12);
+        assert_eq!(complete(&tracker, 12, true), None);
+    }
+}
+
+// endregion: --- Tests
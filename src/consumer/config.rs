@@ -0,0 +1,45 @@
+use std::time::Duration;
+
+use crate::config::kafka_config;
+
+use super::{AssignmentStrategy, CommitStrategy, RateLimit, StartPosition, Subscription};
+
+pub struct ConsumerConfig {
+    pub uri: String,
+    pub group_id: String,
+    pub subscription: Subscription,
+    pub offset_reset: String,
+    pub commit_strategy: CommitStrategy,
+    /// Overrides the committed offset on the first assignment of each partition,
+    /// also used for explicitly assigned partitions without a position
+    pub start_position: Option<StartPosition>,
+    /// Pauses fetching while this many messages wait for their
+    /// [`Acknowledgement`](super::Acknowledgement) (manual commits only)
+    pub max_in_flight: Option<usize>,
+    /// `None` keeps the librdkafka default
+    pub assignment_strategy: Option<AssignmentStrategy>,
+    /// Limits the messages handed to the handler per second
+    pub rate_limit: Option<RateLimit>,
+    /// Cancels a handler running longer and fails its message
+    pub handler_timeout: Option<Duration>,
+    /// Skips records of aborted and still open transactions (librdkafka default)
+    pub read_committed: bool,
+}
+
+impl Default for ConsumerConfig {
+    fn default() -> Self {
+        Self {
+            uri: kafka_config().KAFKA_URI.clone(),
+            group_id: kafka_config().KAFKA_GROUP_ID.clone(),
+            subscription: Subscription::default(),
+            offset_reset: "earliest".to_string(),
+            commit_strategy: CommitStrategy::default(),
+            start_position: None,
+            max_in_flight: None,
+            assignment_strategy: None,
+            rate_limit: None,
+            handler_timeout: None,
+            read_committed: true,
+        }
+    }
+}
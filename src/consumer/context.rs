@@ -0,0 +1,329 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex, MutexGuard, RwLock},
+};
+
+use rdkafka::{
+    consumer::{BaseConsumer, CommitMode, Consumer, ConsumerContext, RebalanceProtocol},
+    error::{KafkaError, KafkaResult, RDKafkaErrorCode},
+    types::RDKafkaRespErr,
+    ClientContext, TopicPartitionList,
+};
+
+use super::{commit::AckTracker, CommitStrategy, ConsumerConfig, RebalanceListener, StartPosition};
+
+/// Consumer context applying the configured [`StartPosition`] the first time
+/// a partition is assigned to this consumer, keeping newly assigned
+/// partitions paused while the consumer is paused and calling the
+/// [`RebalanceListener`] hooks.
+pub struct KafkaContext {
+    start_position: Option<StartPosition>,
+    started: Mutex<HashSet<(String, i32)>>,
+    paused: Mutex<PauseState>,
+    commit_stored_on_revoke: bool,
+    listener: RwLock<Option<Arc<dyn RebalanceListener>>>,
+    pub(crate) acks: AckTracker,
+}
+
+impl KafkaContext {
+    pub(crate) fn new(config: &ConsumerConfig) -> Self {
+        Self {
+            start_position: config.start_position,
+            started: Mutex::new(HashSet::new()),
+            paused: Mutex::new(PauseState::default()),
+            commit_stored_on_revoke: matches!(
+                config.commit_strategy,
+                CommitStrategy::Periodic { .. }
+            ),
+            listener: RwLock::new(None),
+            acks: AckTracker::default(),
+        }
+    }
+
+    pub(crate) fn set_listener(&self, listener: Arc<dyn RebalanceListener>) {
+        *self.listener.write().unwrap_or_else(|e| e.into_inner()) = Some(listener);
+    }
+
+    fn listener(&self) -> Option<Arc<dyn RebalanceListener>> {
+        self.listener
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Pause state, held while pausing or resuming partitions so the state
+    /// and the fetching of the consumer can't diverge
+    pub(crate) fn pause_state(&self) -> MutexGuard<'_, PauseState> {
+        self.paused.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn apply_start_position(&self, consumer: &BaseConsumer<Self>, tpl: &mut TopicPartitionList) {
+        let Some(start_position) = self.start_position else {
+            return;
+        };
+        let mut started = self.started.lock().unwrap_or_else(|e| e.into_inner());
+
+        let partitions = tpl
+            .elements()
+            .iter()
+            .map(|elem| (elem.topic().to_string(), elem.partition()))
+            .filter(|partition| !started.contains(partition))
+            .collect::<Vec<_>>();
+
+        for (topic, partition) in partitions {
+            match start_position.resolve(consumer, &topic, partition) {
+                Ok(offset) => {
+                    if let Err(e) = tpl.set_partition_offset(&topic, partition, offset) {
+                        tracing::error!("Failed to set start offset for {topic}/{partition}: {e}");
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("Failed to resolve start offset for {topic}/{partition}: {e}")
+                }
+            }
+            started.insert((topic, partition));
+        }
+    }
+
+    /// Commits offsets stored but not committed yet, before the partitions
+    /// holding them are revoked
+    fn commit_stored(&self, consumer: &BaseConsumer<Self>) {
+        if !self.commit_stored_on_revoke {
+            return;
+        }
+
+        match consumer.commit_consumer_state(CommitMode::Sync) {
+            Ok(()) | Err(KafkaError::ConsumerCommit(RDKafkaErrorCode::NoOffset)) => {}
+            Err(e) => tracing::error!("Failed to commit before revocation: {}", e),
+        }
+    }
+
+    fn assign(consumer: &BaseConsumer<Self>, tpl: &TopicPartitionList) -> KafkaResult<()> {
+        match consumer.rebalance_protocol() {
+            RebalanceProtocol::Cooperative => consumer.incremental_assign(tpl),
+            _ => consumer.assign(tpl),
+        }
+    }
+
+    fn unassign(consumer: &BaseConsumer<Self>, tpl: &TopicPartitionList) -> KafkaResult<()> {
+        match consumer.rebalance_protocol() {
+            RebalanceProtocol::Cooperative => consumer.incremental_unassign(tpl),
+            _ => consumer.unassign(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum PauseReason {
+    User = 1,
+    Backpressure = 1 << 1,
+    RateLimit = 1 << 2,
+}
+
+/// Bitsets of the active [`PauseReason`]s, of the consumer as a whole and of
+/// single partitions. A partition is fetched only while neither is set.
+#[derive(Debug, Default)]
+pub(crate) struct PauseState {
+    reasons: u8,
+    partitions: HashMap<(String, i32), u8>,
+}
+
+impl PauseState {
+    pub(crate) fn is_paused(&self) -> bool {
+        self.reasons != 0
+    }
+
+    fn is_partition_paused(&self, topic: &str, partition: i32) -> bool {
+        self.is_paused()
+            || self
+                .partitions
+                .get(&(topic.to_string(), partition))
+                .is_some_and(|reasons| *reasons != 0)
+    }
+
+    /// Sets a reason pausing the whole consumer, returns the partitions of
+    /// `assignment` to pause (or resume) accordingly. Partitions still paused
+    /// for another reason are left out.
+    pub(crate) fn set_paused(
+        &mut self,
+        reason: PauseReason,
+        paused: bool,
+        assignment: &TopicPartitionList,
+    ) -> TopicPartitionList {
+        self.transition(assignment, |state| {
+            state.reasons = Self::update(state.reasons, reason, paused)
+        })
+    }
+
+    /// Sets a reason pausing single partitions, returns the ones to pause (or
+    /// resume) accordingly
+    pub(crate) fn set_partitions_paused(
+        &mut self,
+        reason: PauseReason,
+        paused: bool,
+        partitions: &TopicPartitionList,
+    ) -> TopicPartitionList {
+        self.transition(partitions, |state| {
+            for elem in partitions.elements() {
+                let key = (elem.topic().to_string(), elem.partition());
+                let reasons = state.partitions.get(&key).copied().unwrap_or(0);
+                match Self::update(reasons, reason, paused) {
+                    0 => state.partitions.remove(&key),
+                    reasons => state.partitions.insert(key, reasons),
+                };
+            }
+        })
+    }
+
+    /// Partitions of `tpl` that must not be fetched
+    fn paused_partitions(&self, tpl: &TopicPartitionList) -> TopicPartitionList {
+        let mut paused = TopicPartitionList::new();
+        for elem in tpl.elements() {
+            if self.is_partition_paused(elem.topic(), elem.partition()) {
+                paused.add_partition(elem.topic(), elem.partition());
+            }
+        }
+
+        paused
+    }
+
+    /// Drops the pause reasons of revoked partitions, they are fetched again
+    /// when assigned back
+    fn forget_partitions(&mut self, tpl: &TopicPartitionList) {
+        for elem in tpl.elements() {
+            self.partitions
+                .remove(&(elem.topic().to_string(), elem.partition()));
+        }
+    }
+
+    /// Applies `update`, returns the partitions of `tpl` it paused or resumed
+    fn transition(
+        &mut self,
+        tpl: &TopicPartitionList,
+        update: impl FnOnce(&mut Self),
+    ) -> TopicPartitionList {
+        let before = self.paused_partitions(tpl);
+        update(self);
+        let after = self.paused_partitions(tpl);
+
+        let mut changed = TopicPartitionList::new();
+        for elem in tpl.elements() {
+            let (topic, partition) = (elem.topic(), elem.partition());
+            if before.find_partition(topic, partition).is_some()
+                != after.find_partition(topic, partition).is_some()
+            {
+                changed.add_partition(topic, partition);
+            }
+        }
+
+        changed
+    }
+
+    fn update(reasons: u8, reason: PauseReason, paused: bool) -> u8 {
+        if paused {
+            reasons | reason as u8
+        } else {
+            reasons & !(reason as u8)
+        }
+    }
+}
+
+impl ClientContext for KafkaContext {}
+
+impl ConsumerContext for KafkaContext {
+    fn rebalance(
+        &self,
+        base_consumer: &BaseConsumer<Self>,
+        err: RDKafkaRespErr,
+        tpl: &mut TopicPartitionList,
+    ) {
+        let listener = self.listener();
+
+        let result = match err {
+            RDKafkaRespErr::RD_KAFKA_RESP_ERR__ASSIGN_PARTITIONS => {
+                self.apply_start_position(base_consumer, tpl);
+                let pause_state = self.pause_state();
+                let paused = pause_state.paused_partitions(tpl);
+                let result = Self::assign(base_consumer, tpl).and_then(|_| {
+                    if paused.count() > 0 {
+                        base_consumer.pause(&paused)
+                    } else {
+                        Ok(())
+                    }
+                });
+                drop(pause_state);
+
+                if let Some(listener) = &listener {
+                    listener.on_assign(base_consumer, tpl);
+                }
+
+                result
+            }
+            RDKafkaRespErr::RD_KAFKA_RESP_ERR__REVOKE_PARTITIONS => {
+                self.pause_state().forget_partitions(tpl);
+                if base_consumer.assignment_lost() {
+                    tracing::warn!("Partitions lost: {:?}", tpl);
+                    if let Some(listener) = &listener {
+                        listener.on_lost(tpl);
+                    }
+                } else {
+                    self.commit_stored(base_consumer);
+                    if let Some(listener) = &listener {
+                        listener.on_revoke(base_consumer, tpl);
+                    }
+                }
+
+                Self::unassign(base_consumer, tpl)
+            }
+            _ => {
+                tracing::error!("Rebalance error: {:?}", err);
+                Self::unassign(base_consumer, tpl)
+            }
+        };
+
+        if let Err(e) = result {
+            tracing::error!("Failed to apply rebalance: {}", e);
+        }
+    }
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn partitions(tpl: &TopicPartitionList) -> Vec<i32> {
+        tpl.elements().iter().map(|elem| elem.partition()).collect()
+    }
+
+    #[test]
+    fn test_pause_state_keeps_user_paused_partitions() {
+        let mut assignment = TopicPartitionList::new();
+        assignment.add_partition("topic", 0);
+        assignment.add_partition("topic", 1);
+        let mut user_paused = TopicPartitionList::new();
+        user_paused.add_partition("topic", 0);
+        let mut state = PauseState::default();
+
+        let changed = state.set_partitions_paused(PauseReason::User, true, &user_paused);
+        assert_eq!(partitions(&changed), vec![0]);
+
+        let changed = state.set_paused(PauseReason::Backpressure, true, &assignment);
+        assert_eq!(partitions(&changed), vec![1]);
+
+        // Resuming user-paused partitions while paused as a whole is deferred
+        let changed = state.set_paused(PauseReason::RateLimit, true, &assignment);
+        assert!(partitions(&changed).is_empty());
+        let changed = state.set_paused(PauseReason::Backpressure, false, &assignment);
+        assert!(partitions(&changed).is_empty());
+        let changed = state.set_paused(PauseReason::RateLimit, false, &assignment);
+        assert_eq!(partitions(&changed), vec![1]);
+
+        let changed = state.set_partitions_paused(PauseReason::User, false, &user_paused);
+        assert_eq!(partitions(&changed), vec![0]);
+        assert!(state.partitions.is_empty());
+    }
+}
+
+// endregion: --- Tests
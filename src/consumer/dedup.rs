@@ -0,0 +1,158 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use rdkafka::{
+    message::{BorrowedMessage, Headers},
+    Message,
+};
+
+use crate::Result;
+
+/// Remembers ids of processed messages, so that redelivered ones can be
+/// skipped. Implement it for external stores (Redis, a DB table, ...).
+#[async_trait]
+pub trait DeduplicationStore: Send + Sync {
+    /// Whether a message with this id was already processed
+    async fn contains(&self, id: &str) -> Result<bool>;
+
+    /// Records a successfully processed message id
+    async fn insert(&self, id: &str) -> Result<()>;
+}
+
+/// What identifies a message for deduplication.
+#[derive(Debug, Clone)]
+pub enum DeduplicationKey {
+    /// Value of the header with this name, falls back to
+    /// [`DeduplicationKey::Offset`] when the header is missing
+    Header(String),
+    /// `topic/partition/offset` of the message
+    Offset,
+}
+
+impl DeduplicationKey {
+    pub(crate) fn id(&self, message: &BorrowedMessage<'_>) -> String {
+        if let Self::Header(name) = self {
+            let value = message.headers().and_then(|headers| {
+                headers
+                    .iter()
+                    .find(|header| header.key == name)
+                    .and_then(|header| header.value)
+            });
+
+            if let Some(value) = value {
+                return String::from_utf8_lossy(value).into_owned();
+            }
+        }
+
+        format!(
+            "{}/{}/{}",
+            message.topic(),
+            message.partition(),
+            message.offset()
+        )
+    }
+}
+
+pub(crate) struct Deduplication {
+    pub(crate) store: Arc<dyn DeduplicationStore>,
+    pub(crate) key: DeduplicationKey,
+}
+
+// region:    --- InMemoryDeduplicationStore
+
+/// Process local [`DeduplicationStore`] forgetting ids after `ttl`.
+pub struct InMemoryDeduplicationStore {
+    ttl: Duration,
+    entries: Mutex<Entries>,
+}
+
+struct Entries {
+    ids: HashMap<String, Instant>,
+    last_purge: Instant,
+}
+
+impl InMemoryDeduplicationStore {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(Entries {
+                ids: HashMap::new(),
+                last_purge: Instant::now(),
+            }),
+        }
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, Entries> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl DeduplicationStore for InMemoryDeduplicationStore {
+    async fn contains(&self, id: &str) -> Result<bool> {
+        let entries = self.entries();
+
+        Ok(entries
+            .ids
+            .get(id)
+            .is_some_and(|inserted| inserted.elapsed() < self.ttl))
+    }
+
+    async fn insert(&self, id: &str) -> Result<()> {
+        let mut entries = self.entries();
+
+        // Expired ids are dropped at most once per ttl
+        if entries.last_purge.elapsed() >= self.ttl {
+            let ttl = self.ttl;
+            entries.ids.retain(|_, inserted| inserted.elapsed() < ttl);
+            entries.last_purge = Instant::now();
+        }
+        entries.ids.insert(id.to_string(), Instant::now());
+
+        Ok(())
+    }
+}
+
+// endregion: --- InMemoryDeduplicationStore
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>; // For tests.
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_in_memory_store_contains() -> Result<()> {
+        let store = InMemoryDeduplicationStore::new(Duration::from_secs(60));
+
+        assert!(!store.contains("topic/0/1").await?);
+        store.insert("topic/0/1").await?;
+        assert!(store.contains("topic/0/1").await?);
+        assert!(!store.contains("topic/0/2").await?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store_ttl() -> Result<()> {
+        let store = InMemoryDeduplicationStore::new(Duration::from_millis(20));
+
+        store.insert("id").await?;
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(!store.contains("id").await?);
+
+        // Expired ids are purged on the next insert
+        store.insert("other").await?;
+        assert_eq!(store.entries().ids.len(), 1);
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
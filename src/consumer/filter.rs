@@ -0,0 +1,176 @@
+use std::sync::Arc;
+
+use rdkafka::{message::Headers, Message, Timestamp};
+
+use crate::codec::encoded_str;
+
+type Predicate = dyn Fn(&MessageView<'_>) -> bool + Send + Sync;
+
+/// Raw message as seen by a [`MessageFilter::Predicate`], taken from any
+/// [`Message`].
+#[derive(Debug)]
+pub struct MessageView<'a> {
+    pub topic: &'a str,
+    pub partition: i32,
+    pub offset: i64,
+    pub timestamp: Timestamp,
+    pub key: Option<&'a [u8]>,
+    pub payload: Option<&'a [u8]>,
+    /// Header names and values, in order
+    pub headers: Vec<(&'a str, Option<&'a [u8]>)>,
+}
+
+impl<'a> MessageView<'a> {
+    pub fn new(message: &'a impl Message) -> Self {
+        Self {
+            topic: message.topic(),
+            partition: message.partition(),
+            offset: message.offset(),
+            timestamp: message.timestamp(),
+            key: message.key(),
+            payload: message.payload(),
+            headers: message
+                .headers()
+                .map(|headers| {
+                    headers
+                        .iter()
+                        .map(|header| (header.key, header.value))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Value of the first header with this name
+    pub fn header(&self, name: &str) -> Option<&'a [u8]> {
+        self.headers
+            .iter()
+            .find(|(key, _)| *key == name)
+            .and_then(|(_, value)| *value)
+    }
+}
+
+/// Condition a message must meet to reach the handler, checked on the raw
+/// message before anything is decoded. Messages filtered out are committed
+/// without invoking the handler.
+#[derive(Clone)]
+pub enum MessageFilter {
+    /// The key, a `String`, starts with the prefix
+    KeyPrefix(String),
+    /// The header is present with exactly this value
+    Header {
+        name: String,
+        value: Vec<u8>,
+    },
+    /// The message timestamp (ms since epoch) is in `[from, to)`, open ends
+    /// are unbounded
+    Timestamp {
+        from: Option<i64>,
+        to: Option<i64>,
+    },
+    Predicate(Arc<Predicate>),
+}
+
+impl MessageFilter {
+    pub fn key_prefix(prefix: impl Into<String>) -> Self {
+        Self::KeyPrefix(prefix.into())
+    }
+
+    pub fn header(name: impl Into<String>, value: impl Into<Vec<u8>>) -> Self {
+        Self::Header {
+            name: name.into(),
+            value: value.into(),
+        }
+    }
+
+    pub fn timestamp(from: Option<i64>, to: Option<i64>) -> Self {
+        Self::Timestamp { from, to }
+    }
+
+    pub fn predicate(predicate: impl Fn(&MessageView<'_>) -> bool + Send + Sync + 'static) -> Self {
+        Self::Predicate(Arc::new(predicate))
+    }
+
+    pub(crate) fn matches(&self, message: &impl Message) -> bool {
+        match self {
+            // Compared on the encoded bytes, the key is only decoded once it
+            // reaches the handler
+            Self::KeyPrefix(prefix) => message
+                .key()
+                .and_then(encoded_str)
+                .is_some_and(|key| key.starts_with(prefix.as_bytes())),
+            Self::Header { name, value } => message.headers().is_some_and(|headers| {
+                headers
+                    .iter()
+                    .any(|header| header.key == name && header.value == Some(value.as_slice()))
+            }),
+            Self::Timestamp { from, to } => {
+                message.timestamp().to_millis().is_some_and(|timestamp| {
+                    from.is_none_or(|from| timestamp >= from) && to.is_none_or(|to| timestamp < to)
+                })
+            }
+            Self::Predicate(predicate) => predicate(&MessageView::new(message)),
+        }
+    }
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use rdkafka::message::{Header, OwnedHeaders, OwnedMessage};
+
+    use super::*;
+    use crate::encode;
+
+    fn message(key: &str, timestamp: i64, headers: OwnedHeaders) -> OwnedMessage {
+        OwnedMessage::new(
+            Some(b"payload".to_vec()),
+            Some(encode(&key.to_string()).unwrap()),
+            "topic".to_string(),
+            Timestamp::CreateTime(timestamp),
+            0,
+            42,
+            Some(headers),
+        )
+    }
+
+    #[test]
+    fn test_key_prefix_filter() {
+        let filter = MessageFilter::key_prefix("user-");
+
+        assert!(filter.matches(&message("user-1", 0, OwnedHeaders::new())));
+        assert!(!filter.matches(&message("order-1", 0, OwnedHeaders::new())));
+        assert!(!filter.matches(&message("user", 0, OwnedHeaders::new())));
+    }
+
+    #[test]
+    fn test_header_and_timestamp_filters() {
+        let headers = OwnedHeaders::new().insert(Header {
+            key: "source",
+            value: Some("billing"),
+        });
+        let message = message("key", 1_000, headers);
+
+        assert!(MessageFilter::header("source", "billing").matches(&message));
+        assert!(!MessageFilter::header("source", "shipping").matches(&message));
+        assert!(MessageFilter::timestamp(Some(1_000), None).matches(&message));
+        assert!(!MessageFilter::timestamp(None, Some(1_000)).matches(&message));
+    }
+
+    #[test]
+    fn test_predicate_filter() {
+        let filter = MessageFilter::predicate(|message| {
+            message.offset == 42 && message.header("source") == Some(b"billing".as_slice())
+        });
+        let headers = OwnedHeaders::new().insert(Header {
+            key: "source",
+            value: Some("billing"),
+        });
+
+        assert!(filter.matches(&message("key", 0, headers)));
+        assert!(!filter.matches(&message("key", 0, OwnedHeaders::new())));
+    }
+}
+
+// endregion: --- Tests
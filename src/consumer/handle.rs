@@ -0,0 +1,202 @@
+use std::{sync::Arc, time::Duration};
+
+use rdkafka::{consumer::Consumer, Offset, TopicPartitionList};
+use tokio::task::JoinHandle;
+
+use super::{context::PauseReason, InnerConsumer, PartitionLag, StartPosition, TIMEOUT};
+use crate::{Error, Result};
+
+/// Cloneable handle controlling a consumer, including one that is already
+/// running in [`KafkaConsumer::consume_with_state`](super::KafkaConsumer::consume_with_state).
+#[derive(Clone)]
+pub struct ConsumerHandle {
+    consumer: Arc<InnerConsumer>,
+}
+
+impl ConsumerHandle {
+    pub(crate) fn new(consumer: Arc<InnerConsumer>) -> Self {
+        Self { consumer }
+    }
+
+    // region:    --- Seek
+
+    /// Seeks an assigned partition, the next message received from it will be
+    /// the one at `position`.
+    pub fn seek_to(&self, topic: &str, partition: i32, position: StartPosition) -> Result<()> {
+        let offset = position.resolve(self.consumer.as_ref(), topic, partition)?;
+        self.consumer.seek(topic, partition, offset, TIMEOUT)?;
+
+        Ok(())
+    }
+
+    pub fn seek(&self, topic: &str, partition: i32, offset: i64) -> Result<()> {
+        self.seek_to(topic, partition, StartPosition::Offset(offset))
+    }
+
+    pub fn seek_to_beginning(&self, topic: &str, partition: i32) -> Result<()> {
+        self.seek_to(topic, partition, StartPosition::Beginning)
+    }
+
+    pub fn seek_to_end(&self, topic: &str, partition: i32) -> Result<()> {
+        self.seek_to(topic, partition, StartPosition::End)
+    }
+
+    /// Seeks to the first offset with a timestamp (ms since epoch) at or after
+    /// `timestamp`
+    pub fn seek_to_timestamp(&self, topic: &str, partition: i32, timestamp: i64) -> Result<()> {
+        self.seek_to(topic, partition, StartPosition::Timestamp(timestamp))
+    }
+
+    // endregion: --- Seek
+
+    // region:    --- Pause
+
+    /// Stops fetching from every assigned partition without leaving the group.
+    /// Partitions assigned later are paused as well until [`Self::resume_all`].
+    pub fn pause_all(&self) -> Result<()> {
+        self.set_paused(PauseReason::User, true)
+    }
+
+    pub fn resume_all(&self) -> Result<()> {
+        self.set_paused(PauseReason::User, false)
+    }
+
+    /// Pauses specific partitions of a topic, they stay paused when the whole
+    /// consumer is resumed. Unlike [`Self::pause_all`] this is not kept across
+    /// rebalances.
+    pub fn pause(&self, topic: &str, partitions: &[i32]) -> Result<()> {
+        self.set_partitions_paused(PauseReason::User, topic, partitions, true)
+    }
+
+    pub fn resume(&self, topic: &str, partitions: &[i32]) -> Result<()> {
+        self.set_partitions_paused(PauseReason::User, topic, partitions, false)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.consumer.context().pause_state().is_paused()
+    }
+
+    pub(crate) fn set_paused(&self, reason: PauseReason, paused: bool) -> Result<()> {
+        let mut state = self.consumer.context().pause_state();
+        let changed = state.set_paused(reason, paused, &self.consumer.assignment()?);
+
+        self.apply_pause(&changed, paused)
+    }
+
+    pub(crate) fn set_partitions_paused(
+        &self,
+        reason: PauseReason,
+        topic: &str,
+        partitions: &[i32],
+        paused: bool,
+    ) -> Result<()> {
+        let mut state = self.consumer.context().pause_state();
+        let changed =
+            state.set_partitions_paused(reason, paused, &Self::partition_list(topic, partitions));
+
+        self.apply_pause(&changed, paused)
+    }
+
+    /// Called with the pause state locked
+    fn apply_pause(&self, tpl: &TopicPartitionList, paused: bool) -> Result<()> {
+        if tpl.count() == 0 {
+            return Ok(());
+        }
+
+        if paused {
+            self.consumer.pause(tpl)?;
+        } else {
+            self.consumer.resume(tpl)?;
+        }
+
+        Ok(())
+    }
+
+    fn partition_list(topic: &str, partitions: &[i32]) -> TopicPartitionList {
+        let mut tpl = TopicPartitionList::new();
+        for partition in partitions {
+            tpl.add_partition(topic, *partition);
+        }
+
+        tpl
+    }
+
+    // endregion: --- Pause
+
+    // region:    --- Lag
+
+    /// Committed offset, position, high watermark and lag of every assigned
+    /// partition. Blocks the thread on broker requests, up to 10s per
+    /// partition: use [`lag_async`](Self::lag_async) from async code.
+    pub fn lag(&self) -> Result<Vec<PartitionLag>> {
+        let assignment = self.consumer.assignment()?;
+        let committed = self
+            .consumer
+            .committed_offsets(assignment.clone(), TIMEOUT)?;
+        let position = self.consumer.position()?;
+
+        let offset = |tpl: &TopicPartitionList, topic: &str, partition: i32| {
+            tpl.find_partition(topic, partition)
+                .and_then(|elem| match elem.offset() {
+                    Offset::Offset(offset) => Some(offset),
+                    _ => None,
+                })
+        };
+
+        assignment
+            .elements()
+            .iter()
+            .map(|elem| {
+                let (topic, partition) = (elem.topic(), elem.partition());
+                let (low_watermark, high_watermark) =
+                    self.consumer.fetch_watermarks(topic, partition, TIMEOUT)?;
+
+                let committed = offset(&committed, topic, partition);
+                let position = offset(&position, topic, partition);
+                let consumed = position.or(committed).unwrap_or(low_watermark);
+
+                Ok(PartitionLag {
+                    topic: topic.to_string(),
+                    partition,
+                    committed,
+                    position,
+                    high_watermark,
+                    lag: (high_watermark - consumed).max(0),
+                })
+            })
+            .collect()
+    }
+
+    /// [`lag`](Self::lag) computed on the blocking thread pool
+    pub async fn lag_async(&self) -> Result<Vec<PartitionLag>> {
+        let handle = self.clone();
+        tokio::task::spawn_blocking(move || handle.lag())
+            .await
+            .map_err(|e| Error::BlockingTask(e.to_string()))?
+    }
+
+    /// Computes the lag every `interval` on the blocking thread pool and hands
+    /// it to `report`, until the returned task is aborted
+    pub fn report_lag(
+        &self,
+        interval: Duration,
+        report: impl Fn(Vec<PartitionLag>) + Send + Sync + 'static,
+    ) -> JoinHandle<()> {
+        let handle = self.clone();
+        let report = Arc::new(report);
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+
+                match handle.lag_async().await {
+                    Ok(lag) => report(lag),
+                    Err(e) => tracing::error!("Failed to compute consumer lag: {}", e),
+                }
+            }
+        })
+    }
+
+    // endregion: --- Lag
+}
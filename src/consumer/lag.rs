@@ -0,0 +1,14 @@
+/// Offsets of one assigned partition and how far behind the consumer is.
+#[derive(Debug, Clone)]
+pub struct PartitionLag {
+    pub topic: String,
+    pub partition: i32,
+    /// `None` when the group has no committed offset for the partition yet
+    pub committed: Option<i64>,
+    /// Offset of the next message to be consumed, `None` before the first fetch
+    pub position: Option<i64>,
+    pub high_watermark: i64,
+    /// Messages between the position (or the committed offset before the
+    /// first fetch) and the high watermark
+    pub lag: i64,
+}
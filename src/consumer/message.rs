@@ -0,0 +1,91 @@
+use rdkafka::{
+    message::{BorrowedHeaders, BorrowedMessage, Headers},
+    Message,
+};
+
+use super::Acknowledgement;
+use crate::MessageHeaders;
+
+/// Message handed to [`StateReceiver::handle`](super::StateReceiver::handle)
+/// and [`Receiver::handle`](super::Receiver::handle).
+pub struct ReceivedMessage<'a> {
+    topic: &'a str,
+    partition: i32,
+    offset: i64,
+    key: &'a str,
+    payload: Option<&'a [u8]>,
+    headers: Option<&'a BorrowedHeaders>,
+    ack: Option<Acknowledgement>,
+}
+
+impl<'a> ReceivedMessage<'a> {
+    pub(crate) fn new(
+        message: &'a BorrowedMessage<'a>,
+        key: &'a str,
+        ack: Option<Acknowledgement>,
+    ) -> Self {
+        Self {
+            topic: message.topic(),
+            partition: message.partition(),
+            offset: message.offset(),
+            key,
+            payload: message.payload(),
+            headers: message.headers(),
+            ack,
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn from_owned(message: &'a rdkafka::message::OwnedMessage, key: &'a str) -> Self {
+        Self {
+            topic: message.topic(),
+            partition: message.partition(),
+            offset: message.offset(),
+            key,
+            payload: message.payload(),
+            headers: message
+                .headers()
+                .map(rdkafka::message::OwnedHeaders::as_borrowed),
+            ack: None,
+        }
+    }
+
+    /// Topic the message was read from, useful with pattern subscriptions
+    pub fn topic(&self) -> &'a str {
+        self.topic
+    }
+
+    pub fn partition(&self) -> i32 {
+        self.partition
+    }
+
+    pub fn offset(&self) -> i64 {
+        self.offset
+    }
+
+    pub fn key(&self) -> &'a str {
+        self.key
+    }
+
+    pub fn payload(&self) -> Option<&'a [u8]> {
+        self.payload
+    }
+
+    /// First value of the header
+    pub fn header(&self, key: &str) -> Option<&'a [u8]> {
+        self.headers?
+            .iter()
+            .find(|header| header.key == key)
+            .and_then(|header| header.value)
+    }
+
+    pub fn headers(&self) -> MessageHeaders {
+        self.headers.map(MessageHeaders::from).unwrap_or_default()
+    }
+
+    /// Takes the acknowledgement handle. Only present with
+    /// [`CommitStrategy::Manual`](super::CommitStrategy::Manual).
+    pub fn take_ack(&mut self) -> Option<Acknowledgement> {
+        self.ack.take()
+    }
+}
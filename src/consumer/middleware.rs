@@ -0,0 +1,272 @@
+use std::{
+    any::Any,
+    future::Future,
+    panic::AssertUnwindSafe,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use futures::FutureExt;
+use tracing::Instrument;
+
+use super::{ReceivedMessage, StateReceiver};
+use crate::{Error, Result};
+
+/// Wraps the handler invocation, for cross-cutting concerns like logging,
+/// timing, tracing spans or auth checks. Call `next.run(message)` to pass the
+/// message on, or return without calling it to stop it here.
+#[async_trait]
+pub trait Middleware: Send + Sync {
+    async fn call(&self, message: ReceivedMessage<'_>, next: Next<'_>) -> Result<()>;
+}
+
+/// The rest of the middleware chain, ending with the handler.
+pub struct Next<'a> {
+    middlewares: &'a [Arc<dyn Middleware>],
+    endpoint: &'a dyn Endpoint,
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(middlewares: &'a [Arc<dyn Middleware>], endpoint: &'a dyn Endpoint) -> Self {
+        Self {
+            middlewares,
+            endpoint,
+        }
+    }
+
+    pub async fn run(self, message: ReceivedMessage<'_>) -> Result<()> {
+        match self.middlewares.split_first() {
+            Some((middleware, rest)) => {
+                middleware
+                    .call(message, Next::new(rest, self.endpoint))
+                    .await
+            }
+            None => self.endpoint.call(message).await,
+        }
+    }
+}
+
+#[async_trait]
+pub(crate) trait Endpoint: Send + Sync {
+    async fn call(&self, message: ReceivedMessage<'_>) -> Result<()>;
+}
+
+/// Calls [`StateReceiver::handle`] at the end of the chain
+pub(crate) struct Handler<'s, T: StateReceiver> {
+    pub(crate) state: &'s T::State,
+}
+
+#[async_trait]
+impl<T: StateReceiver> Endpoint for Handler<'_, T> {
+    async fn call(&self, message: ReceivedMessage<'_>) -> Result<()> {
+        T::handle(message, self.state).await
+    }
+}
+
+/// Runs the middleware chain, failing the message instead of killing the
+/// consumer when it panics. Past `timeout` the chain is cancelled (dropped)
+/// and fails the message as well.
+pub(crate) async fn run_guarded(
+    run: impl Future<Output = Result<()>>,
+    timeout: Option<Duration>,
+) -> Result<()> {
+    let run = AssertUnwindSafe(run).catch_unwind().map(|result| {
+        result.unwrap_or_else(|panic| Err(Error::HandlerPanic(panic_message(panic))))
+    });
+
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, run)
+            .await
+            .unwrap_or(Err(Error::HandlerTimeout(timeout))),
+        None => run.await,
+    }
+}
+
+fn panic_message(panic: Box<dyn Any + Send>) -> String {
+    match panic.downcast::<String>() {
+        Ok(message) => *message,
+        Err(panic) => match panic.downcast_ref::<&str>() {
+            Some(message) => message.to_string(),
+            None => "unknown panic".to_string(),
+        },
+    }
+}
+
+// region:    --- TracingMiddleware
+
+/// Runs the handler in a span with the message coordinates and logs how long
+/// it took.
+pub struct TracingMiddleware;
+
+#[async_trait]
+impl Middleware for TracingMiddleware {
+    async fn call(&self, message: ReceivedMessage<'_>, next: Next<'_>) -> Result<()> {
+        let span = tracing::info_span!(
+            "kafka_message",
+            topic = message.topic(),
+            partition = message.partition(),
+            offset = message.offset(),
+            key = message.key(),
+        );
+
+        async move {
+            let start = Instant::now();
+            let result = next.run(message).await;
+            tracing::debug!("Message processed in {:?}", start.elapsed());
+
+            result
+        }
+        .instrument(span)
+        .await
+    }
+}
+
+// endregion: --- TracingMiddleware
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use rdkafka::{message::OwnedMessage, Timestamp};
+
+    use super::*;
+
+    type Calls = Arc<Mutex<Vec<String>>>;
+
+    struct Record {
+        name: &'static str,
+        calls: Calls,
+        pass_on: bool,
+    }
+
+    #[async_trait]
+    impl Middleware for Record {
+        async fn call(&self, message: ReceivedMessage<'_>, next: Next<'_>) -> Result<()> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("{} before", self.name));
+            if !self.pass_on {
+                return Ok(());
+            }
+            let result = next.run(message).await;
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("{} after", self.name));
+
+            result
+        }
+    }
+
+    struct RecordEndpoint(Calls);
+
+    #[async_trait]
+    impl Endpoint for RecordEndpoint {
+        async fn call(&self, message: ReceivedMessage<'_>) -> Result<()> {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("handler {}", message.key()));
+            Ok(())
+        }
+    }
+
+    async fn run_chain(pass_on: [bool; 2]) -> Vec<String> {
+        let calls = Calls::default();
+        let middlewares: Vec<Arc<dyn Middleware>> = vec![
+            Arc::new(Record {
+                name: "outer",
+                calls: calls.clone(),
+                pass_on: pass_on[0],
+            }),
+            Arc::new(Record {
+                name: "inner",
+                calls: calls.clone(),
+                pass_on: pass_on[1],
+            }),
+        ];
+        let endpoint = RecordEndpoint(calls.clone());
+        let message = OwnedMessage::new(
+            None,
+            None,
+            "topic".to_string(),
+            Timestamp::NotAvailable,
+            0,
+            0,
+            None,
+        );
+
+        Next::new(&middlewares, &endpoint)
+            .run(ReceivedMessage::from_owned(&message, "key"))
+            .await
+            .unwrap();
+
+        let calls = calls.lock().unwrap().clone();
+        calls
+    }
+
+    #[tokio::test]
+    async fn test_middlewares_run_in_order() {
+        assert_eq!(
+            run_chain([true, true]).await,
+            vec![
+                "outer before",
+                "inner before",
+                "handler key",
+                "inner after",
+                "outer after"
+            ]
+        );
+        assert_eq!(
+            run_chain([true, false]).await,
+            vec!["outer before", "inner before", "outer after"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_guarded_turns_panics_into_errors() {
+        let result = run_guarded(
+            async {
+                if true {
+                    panic!("handler failed");
+                }
+                Ok(())
+            },
+            None,
+        )
+        .await;
+        assert!(matches!(result, Err(Error::HandlerPanic(message)) if message == "handler failed"));
+
+        let code = 42;
+        let result = run_guarded(
+            async move {
+                if true {
+                    panic!("handler failed with {code}");
+                }
+                Ok(())
+            },
+            None,
+        )
+        .await;
+        assert!(
+            matches!(result, Err(Error::HandlerPanic(message)) if message == "handler failed with 42")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_guarded_times_out() {
+        let timeout = Duration::from_millis(10);
+
+        let result = run_guarded(std::future::pending(), Some(timeout)).await;
+        assert!(matches!(result, Err(Error::HandlerTimeout(t)) if t == timeout));
+
+        let result = run_guarded(async { Ok(()) }, Some(timeout)).await;
+        assert!(result.is_ok());
+    }
+}
+
+// endregion: --- Tests
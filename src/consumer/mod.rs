@@ -0,0 +1,574 @@
+mod backpressure;
+mod commit;
+mod config;
+mod context;
+mod dedup;
+mod filter;
+mod handle;
+mod lag;
+mod message;
+mod middleware;
+mod pipeline;
+mod position;
+mod rate_limit;
+mod rebalance;
+mod stream;
+mod subscription;
+
+// region:    --- Modules
+
+use std::{marker::PhantomData, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use futures::Stream;
+use rdkafka::{
+    consumer::{CommitMode, Consumer, StreamConsumer},
+    message::BorrowedMessage,
+    ClientConfig, Message, Offset, TopicPartitionList,
+};
+use tokio::time::Interval;
+
+use crate::{decode, Decode, Error, Result};
+use backpressure::InFlight;
+use commit::AckMode;
+use context::PauseReason;
+use dedup::Deduplication;
+use middleware::{run_guarded, Handler};
+use rate_limit::RateLimiter;
+
+pub use commit::{Acknowledgement, CommitStrategy};
+pub use config::ConsumerConfig;
+pub use context::KafkaContext;
+pub use dedup::{DeduplicationKey, DeduplicationStore, InMemoryDeduplicationStore};
+pub use filter::{MessageFilter, MessageView};
+pub use handle::ConsumerHandle;
+pub use lag::PartitionLag;
+pub use message::ReceivedMessage;
+pub use middleware::{Middleware, Next, TracingMiddleware};
+pub use pipeline::Transformer;
+pub use position::StartPosition;
+pub use rate_limit::{RateLimit, RateLimitScope};
+pub use rebalance::{AssignmentStrategy, RebalanceListener};
+pub use stream::TypedMessage;
+pub use subscription::{PartitionAssignment, Subscription};
+
+// endregion: --- Modules
+
+pub(crate) type InnerConsumer = StreamConsumer<KafkaContext>;
+
+/// Timeout for blocking broker requests (seek, offset lookups)
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Rate limited waits at least this long pause fetching, shorter ones are not
+/// worth dropping the prefetched messages
+const THROTTLE_PAUSE_AFTER: Duration = Duration::from_millis(500);
+
+#[async_trait]
+pub trait Receiver: Sized + Send + Sync {
+    async fn process(key: &str, payload: Option<&[u8]>) -> Result<()>;
+
+    /// Called for tombstones (null payload) instead of `process`
+    async fn on_delete(key: &str) -> Result<()> {
+        Self::process(key, None).await
+    }
+
+    /// Called by the consumer for every message. Commits the acknowledgement
+    /// (if any) after a successful `process`/`on_delete`, override to commit
+    /// it later.
+    async fn handle(mut message: ReceivedMessage<'_>) -> Result<()> {
+        match message.payload() {
+            Some(payload) => Self::process(message.key(), Some(payload)).await?,
+            None => Self::on_delete(message.key()).await?,
+        }
+
+        if let Some(ack) = message.take_ack() {
+            ack.commit()?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+pub trait StateReceiver: Sized + Send + Sync {
+    type State: Send + Sync;
+
+    async fn process(key: &str, payload: Option<&[u8]>, state: &Self::State) -> Result<()>;
+
+    /// Called for tombstones (null payload) instead of `process`
+    async fn on_delete(key: &str, state: &Self::State) -> Result<()> {
+        Self::process(key, None, state).await
+    }
+
+    /// Called by the consumer for every message. Commits the acknowledgement
+    /// (if any) after a successful `process`/`on_delete`, override to commit
+    /// it later.
+    async fn handle(mut message: ReceivedMessage<'_>, state: &Self::State) -> Result<()> {
+        match message.payload() {
+            Some(payload) => Self::process(message.key(), Some(payload), state).await?,
+            None => Self::on_delete(message.key(), state).await?,
+        }
+
+        if let Some(ack) = message.take_ack() {
+            ack.commit()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Runs a [`Receiver`] through the [`StateReceiver`] consuming loop
+struct Stateless<T>(PhantomData<T>);
+
+#[async_trait]
+impl<T: Receiver> StateReceiver for Stateless<T> {
+    type State = ();
+
+    async fn process(key: &str, payload: Option<&[u8]>, _state: &Self::State) -> Result<()> {
+        T::process(key, payload).await
+    }
+
+    async fn on_delete(key: &str, _state: &Self::State) -> Result<()> {
+        T::on_delete(key).await
+    }
+
+    async fn handle(message: ReceivedMessage<'_>, _state: &Self::State) -> Result<()> {
+        T::handle(message).await
+    }
+}
+
+#[async_trait]
+pub trait ConsumerLike: Send + Sync {
+    async fn consume_with_state<R>(self, state: Arc<R::State>) -> Result<()>
+    where
+        R: StateReceiver + Send + Sync,
+        R::State: Send + Sync;
+}
+
+// Реализация для FutureProducer
+
+#[async_trait]
+impl ConsumerLike for crate::consumer::KafkaConsumer {
+    async fn consume_with_state<R>(self, state: Arc<R::State>) -> Result<()>
+    where
+        R: StateReceiver + Send + Sync,
+        R::State: Send + Sync,
+    {
+        // Просто делегируем к существующему методу
+        crate::consumer::KafkaConsumer::consume_with_state::<R>(self, state).await
+    }
+}
+
+pub struct KafkaConsumer {
+    consumer: Arc<InnerConsumer>,
+    commit_strategy: CommitStrategy,
+    in_flight: Option<InFlight>,
+    deduplication: Option<Deduplication>,
+    filters: Vec<MessageFilter>,
+    rate_limiter: Option<RateLimiter>,
+    middlewares: Vec<Arc<dyn Middleware>>,
+    handler_timeout: Option<Duration>,
+}
+
+/// What happened to a received message
+enum Outcome {
+    Processed,
+    /// The handler was not invoked, e.g. for a duplicate or a filtered out message
+    Skipped,
+    Failed(Error),
+}
+
+impl KafkaConsumer {
+    #[allow(clippy::should_implement_trait)]
+    pub fn default() -> Result<Self> {
+        let config = ConsumerConfig::default();
+
+        Self::new(&config)
+    }
+
+    pub fn new(config: &ConsumerConfig) -> Result<Self> {
+        let mut client_config = ClientConfig::new();
+        client_config
+            .set("bootstrap.servers", &config.uri)
+            .set("group.id", &config.group_id)
+            .set("auto.offset.reset", &config.offset_reset)
+            .set(
+                "isolation.level",
+                if config.read_committed {
+                    "read_committed"
+                } else {
+                    "read_uncommitted"
+                },
+            );
+        config.commit_strategy.apply(&mut client_config);
+        if let Some(strategy) = config.assignment_strategy {
+            client_config.set("partition.assignment.strategy", strategy.as_str());
+        }
+
+        let context = KafkaContext::new(config);
+        let consumer: InnerConsumer = client_config.create_with_context(context)?;
+
+        let mut kafka_consumer = Self {
+            consumer: Arc::new(consumer),
+            commit_strategy: config.commit_strategy,
+            in_flight: config.max_in_flight.map(InFlight::new),
+            deduplication: None,
+            filters: Vec::new(),
+            rate_limiter: config.rate_limit.map(RateLimiter::new).transpose()?,
+            middlewares: Vec::new(),
+            handler_timeout: config.handler_timeout,
+        };
+
+        match &config.subscription {
+            Subscription::Topics(topics) => kafka_consumer.subscribe(topics)?,
+            Subscription::Patterns(patterns) => kafka_consumer.subscribe_patterns(patterns)?,
+            Subscription::Assignment(partitions) => {
+                let partitions = partitions
+                    .iter()
+                    .map(|p| PartitionAssignment {
+                        position: p.position.or(config.start_position),
+                        ..p.clone()
+                    })
+                    .collect::<Vec<_>>();
+                kafka_consumer.assign(&partitions)?
+            }
+        }
+
+        Ok(kafka_consumer)
+    }
+
+    fn is_fatal_error(error: &rdkafka::error::KafkaError) -> bool {
+        matches!(
+            error,
+            rdkafka::error::KafkaError::ClientConfig(_, _, _, _)
+                | rdkafka::error::KafkaError::ClientCreation(_)
+                | rdkafka::error::KafkaError::Subscription(_)
+        )
+    }
+
+    pub async fn consume_with_state<T: StateReceiver>(self, state: Arc<T::State>) -> Result<()> {
+        let mut commit_interval = match self.commit_strategy {
+            CommitStrategy::Periodic { interval, .. } => Some(tokio::time::interval(interval)),
+            _ => None,
+        };
+        let mut has_stored_offsets = false;
+        let mut backpressured = false;
+
+        loop {
+            if !backpressured && self.in_flight.as_ref().is_some_and(InFlight::is_full) {
+                tracing::warn!("Too many messages in flight, pausing consumer");
+                self.set_paused(PauseReason::Backpressure, true);
+                backpressured = true;
+            }
+
+            let received = tokio::select! {
+                received = self.consumer.recv() => received,
+                _ = Self::tick(&mut commit_interval) => {
+                    if std::mem::take(&mut has_stored_offsets) {
+                        self.commit_stored();
+                    }
+                    continue;
+                }
+                _ = Self::below_bound(self.in_flight.as_ref()), if backpressured => {
+                    tracing::info!("In-flight messages below the bound, resuming consumer");
+                    self.set_paused(PauseReason::Backpressure, false);
+                    backpressured = false;
+                    continue;
+                }
+            };
+
+            match received {
+                Err(e) => {
+                    tracing::error!("Kafka error: {}", e);
+                    if Self::is_fatal_error(&e) {
+                        break Err(Error::Rdkafka(e));
+                    }
+                }
+                Ok(message) => {
+                    let result = match self.process::<T>(&message, &state).await? {
+                        Outcome::Processed | Outcome::Skipped => self.commit(&message),
+                        Outcome::Failed(e) => {
+                            tracing::error!("Error processing message: {}", e);
+                            continue;
+                        }
+                    };
+
+                    match result {
+                        Ok(()) => has_stored_offsets = commit_interval.is_some(),
+                        Err(e) => tracing::error!("Commit error: {}", e),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Turns the consumer into a stream of decoded messages, for stream
+    /// combinators instead of a receiver. Every message carries an
+    /// [`Acknowledgement`] to commit it with; filters, deduplication, rate
+    /// limiting and middlewares are not applied.
+    pub fn into_stream<M>(self) -> impl Stream<Item = Result<TypedMessage<M>>> + Send
+    where
+        M: Decode<()> + Send + 'static,
+    {
+        stream::typed_messages(self.consumer, self.commit_strategy.ack_mode())
+    }
+
+    // Аналогично для consume без state
+    pub async fn consume<T: Receiver>(self) -> Result<()> {
+        self.consume_with_state::<Stateless<T>>(Arc::new(())).await
+    }
+
+    /// Runs a message through filters, deduplication and the handler. Skipped messages
+    /// are committed like processed ones, except with manual commits where the
+    /// next acknowledgement covers them.
+    async fn process<T: StateReceiver>(
+        &self,
+        message: &BorrowedMessage<'_>,
+        state: &T::State,
+    ) -> Result<Outcome> {
+        if !self.filters.iter().all(|filter| filter.matches(message)) {
+            return Ok(Outcome::Skipped);
+        }
+
+        let dedup_id = match &self.deduplication {
+            Some(dedup) => {
+                let id = dedup.key.id(message);
+                match dedup.store.contains(&id).await {
+                    Ok(true) => {
+                        tracing::debug!("Skipping duplicate message {}", id);
+                        return Ok(Outcome::Skipped);
+                    }
+                    Ok(false) => {}
+                    Err(e) => tracing::error!("Deduplication store error: {}", e),
+                }
+                Some(id)
+            }
+            None => None,
+        };
+
+        // A bad key fails the message instead of stopping the consumer
+        let key = match message.key().ok_or(Error::KeyMissing) {
+            Ok(key) => key,
+            Err(e) => return Ok(Outcome::Failed(e)),
+        };
+        let key = match decode::<String>(key) {
+            Ok(key) => key,
+            Err(e) => return Ok(Outcome::Failed(e.into())),
+        };
+
+        if let Some(limiter) = &self.rate_limiter {
+            self.throttle(message, limiter.scope(), limiter.reserve(&key))
+                .await;
+        }
+
+        let ack = match self.commit_strategy {
+            CommitStrategy::Manual(mode) => Some(Acknowledgement::new(
+                self.consumer.clone(),
+                message.topic(),
+                message.partition(),
+                message.offset(),
+                AckMode::Commit(mode),
+                self.in_flight.as_ref().map(InFlight::acquire),
+            )),
+            _ => None,
+        };
+        let received = ReceivedMessage::new(message, &key, ack);
+
+        let handler = Handler::<T> { state };
+        let run = Next::new(&self.middlewares, &handler).run(received);
+        if let Err(e) = run_guarded(run, self.handler_timeout).await {
+            return Ok(Outcome::Failed(e));
+        }
+
+        if let (Some(dedup), Some(id)) = (&self.deduplication, dedup_id) {
+            if let Err(e) = dedup.store.insert(&id).await {
+                tracing::error!("Deduplication store error: {}", e);
+            }
+        }
+
+        Ok(Outcome::Processed)
+    }
+
+    /// Commits (or stores) the offset of a processed message according to the
+    /// commit strategy
+    fn commit(&self, message: &BorrowedMessage<'_>) -> Result<()> {
+        match self.commit_strategy {
+            CommitStrategy::PerMessage(mode) => self.consumer.commit_message(message, mode)?,
+            CommitStrategy::AutoStore | CommitStrategy::Periodic { .. } => {
+                self.consumer.store_offset_from_message(message)?
+            }
+            CommitStrategy::Manual(_) => {}
+        }
+
+        Ok(())
+    }
+
+    fn commit_stored(&self) {
+        if let CommitStrategy::Periodic { mode, .. } = self.commit_strategy {
+            if let Err(e) = self.consumer.commit_consumer_state(mode) {
+                tracing::error!("Commit error: {}", e);
+            }
+        }
+    }
+
+    /// Waits out the rate limit, pausing fetching during long waits: of the
+    /// whole consumer for a global limit, of the partition of the message for
+    /// a per-key one
+    async fn throttle(&self, message: &BorrowedMessage<'_>, scope: RateLimitScope, wait: Duration) {
+        if wait.is_zero() {
+            return;
+        }
+
+        let pause = wait >= THROTTLE_PAUSE_AFTER;
+        if pause {
+            tracing::debug!("Rate limited for {:?}, pausing fetching", wait);
+            self.set_rate_limited(message, scope, true);
+        }
+
+        tokio::time::sleep(wait).await;
+
+        if pause {
+            self.set_rate_limited(message, scope, false);
+        }
+    }
+
+    fn set_rate_limited(&self, message: &BorrowedMessage<'_>, scope: RateLimitScope, paused: bool) {
+        let result = match scope {
+            RateLimitScope::Global => self.handle().set_paused(PauseReason::RateLimit, paused),
+            RateLimitScope::PerKey => self.handle().set_partitions_paused(
+                PauseReason::RateLimit,
+                message.topic(),
+                &[message.partition()],
+                paused,
+            ),
+        };
+
+        if let Err(e) = result {
+            tracing::error!("Failed to pause/resume consumer: {}", e);
+        }
+    }
+
+    fn set_paused(&self, reason: PauseReason, paused: bool) {
+        if let Err(e) = self.handle().set_paused(reason, paused) {
+            tracing::error!("Failed to pause/resume consumer: {}", e);
+        }
+    }
+
+    async fn below_bound(in_flight: Option<&InFlight>) {
+        match in_flight {
+            Some(in_flight) => in_flight.below_bound().await,
+            None => std::future::pending().await,
+        }
+    }
+
+    async fn tick(interval: &mut Option<Interval>) {
+        match interval {
+            Some(interval) => {
+                interval.tick().await;
+            }
+            None => std::future::pending().await,
+        }
+    }
+
+    pub fn handle(&self) -> ConsumerHandle {
+        ConsumerHandle::new(self.consumer.clone())
+    }
+
+    pub fn pause_all(&self) -> Result<()> {
+        self.handle().pause_all()
+    }
+
+    pub fn resume_all(&self) -> Result<()> {
+        self.handle().resume_all()
+    }
+
+    /// Blocks on broker requests, see [`ConsumerHandle::lag`]
+    pub fn lag(&self) -> Result<Vec<PartitionLag>> {
+        self.handle().lag()
+    }
+
+    pub fn seek(&self, topic: &str, partition: i32, offset: i64) -> Result<()> {
+        self.handle().seek(topic, partition, offset)
+    }
+
+    pub fn seek_to_beginning(&self, topic: &str, partition: i32) -> Result<()> {
+        self.handle().seek_to_beginning(topic, partition)
+    }
+
+    pub fn seek_to_end(&self, topic: &str, partition: i32) -> Result<()> {
+        self.handle().seek_to_end(topic, partition)
+    }
+
+    pub fn seek_to_timestamp(&self, topic: &str, partition: i32, timestamp: i64) -> Result<()> {
+        self.handle().seek_to_timestamp(topic, partition, timestamp)
+    }
+
+    pub fn subscribe(&mut self, topics: &[impl AsRef<str>]) -> Result<()> {
+        let topics = topics.iter().map(|t| t.as_ref()).collect::<Vec<&str>>();
+        self.consumer.subscribe(&topics)?;
+
+        Ok(())
+    }
+
+    /// Sets the hooks called when partitions are assigned, revoked or lost
+    pub fn rebalance_listener(&mut self, listener: impl RebalanceListener + 'static) {
+        self.consumer.context().set_listener(Arc::new(listener));
+    }
+
+    /// Subscribes to every topic matching one of the regexes, the `^` librdkafka
+    /// uses to tell patterns from topic names is added when missing
+    pub fn subscribe_patterns(&mut self, patterns: &[impl AsRef<str>]) -> Result<()> {
+        let patterns = patterns
+            .iter()
+            .map(|p| match p.as_ref() {
+                p if p.starts_with('^') => p.to_string(),
+                p => format!("^{p}"),
+            })
+            .collect::<Vec<_>>();
+
+        self.subscribe(&patterns)
+    }
+
+    /// Reads exactly the given partitions instead of joining the consumer group,
+    /// replacing the previous assignment
+    pub fn assign(&mut self, partitions: &[PartitionAssignment]) -> Result<()> {
+        let mut tpl = TopicPartitionList::new();
+        for p in partitions {
+            let offset = match p.position {
+                Some(position) => {
+                    position.resolve(self.consumer.as_ref(), &p.topic, p.partition)?
+                }
+                None => Offset::Stored,
+            };
+            tpl.add_partition_offset(&p.topic, p.partition, offset)?;
+        }
+        self.consumer.assign(&tpl)?;
+
+        Ok(())
+    }
+
+    /// Skips messages the store already saw processed, e.g. redelivered after a
+    /// rebalance
+    pub fn deduplicate(&mut self, store: impl DeduplicationStore + 'static, key: DeduplicationKey) {
+        self.deduplication = Some(Deduplication {
+            store: Arc::new(store),
+            key,
+        });
+    }
+
+    /// Adds a filter, only messages matching every filter reach the handler
+    pub fn filter(&mut self, filter: MessageFilter) {
+        self.filters.push(filter);
+    }
+
+    /// Appends a middleware, the first one added is the outermost
+    pub fn middleware(&mut self, middleware: impl Middleware + 'static) {
+        self.middlewares.push(Arc::new(middleware));
+    }
+
+    pub fn commit_mode(&mut self, commit_mode: CommitMode) -> Result<()> {
+        self.commit_strategy = self.commit_strategy.with_mode(commit_mode);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,159 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use rdkafka::{consumer::Consumer, message::BorrowedMessage, Message, Offset, TopicPartitionList};
+
+use super::{KafkaConsumer, Outcome, ReceivedMessage, TIMEOUT};
+use crate::{
+    decode,
+    producer::{is_retriable, ProducerRecord, RetryPolicy, TransactionalProducer},
+    Error, Result,
+};
+
+/// Consume-transform-produce handler: turns every consumed message into the
+/// records to produce, see [`KafkaConsumer::consume_transform`].
+#[async_trait]
+pub trait Transformer: Sized + Send + Sync {
+    type State: Send + Sync;
+
+    async fn transform(
+        message: &ReceivedMessage<'_>,
+        state: &Self::State,
+    ) -> Result<Vec<ProducerRecord>>;
+}
+
+impl KafkaConsumer {
+    /// Exactly-once loop: the records a message is transformed into and the
+    /// offset of the message are committed in one producer transaction. A
+    /// transaction failing on a transient error is aborted and the message read
+    /// again after a backoff; a failing transformer, an undecodable key or a
+    /// permanent error skips the message. Filters apply, deduplication, rate
+    /// limiting, middlewares and the commit strategy don't.
+    ///
+    /// Returns when the producer was fenced or hit a fatal error.
+    pub async fn consume_transform<T: Transformer>(
+        self,
+        producer: &TransactionalProducer,
+        state: Arc<T::State>,
+    ) -> Result<()> {
+        let retry_policy = RetryPolicy::default();
+        let mut retries = 0;
+
+        loop {
+            let message = match self.consumer.recv().await {
+                Ok(message) => message,
+                Err(e) => {
+                    tracing::error!("Kafka error: {}", e);
+                    if Self::is_fatal_error(&e) {
+                        break Err(Error::Rdkafka(e));
+                    }
+                    continue;
+                }
+            };
+
+            match self.transform::<T>(&message, producer, &state).await {
+                Ok(Outcome::Processed | Outcome::Skipped) => retries = 0,
+                Ok(Outcome::Failed(e)) => {
+                    retries = 0;
+                    tracing::error!("Error transforming message, skipping it: {}", e);
+                }
+                Err(e @ (Error::ProducerFenced | Error::TransactionFatal(_))) => break Err(e),
+                Err(e) if matches!(e, Error::TransactionAbortable(_)) || is_retriable(&e) => {
+                    let backoff = retry_policy.jittered_backoff(retries);
+                    retries += 1;
+                    tracing::warn!(
+                        "Transaction failed, reading the message again in {:?}: {}",
+                        backoff,
+                        e
+                    );
+                    tokio::time::sleep(backoff).await;
+                    self.consumer.seek(
+                        message.topic(),
+                        message.partition(),
+                        Offset::Offset(message.offset()),
+                        TIMEOUT,
+                    )?;
+                }
+                Err(e) => {
+                    retries = 0;
+                    tracing::error!("Transaction failed, skipping the message: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Runs a message through its transaction, aborting the transaction when
+    /// it fails
+    async fn transform<T: Transformer>(
+        &self,
+        message: &BorrowedMessage<'_>,
+        producer: &TransactionalProducer,
+        state: &T::State,
+    ) -> Result<Outcome> {
+        // Offsets of skipped messages are committed by the next transaction
+        if !self.filters.iter().all(|filter| filter.matches(message)) {
+            return Ok(Outcome::Skipped);
+        }
+
+        let key = match message.key().ok_or(Error::KeyMissing) {
+            Ok(key) => key,
+            Err(e) => return Ok(Outcome::Failed(e)),
+        };
+        let key = match decode::<String>(key) {
+            Ok(key) => key,
+            Err(e) => return Ok(Outcome::Failed(e.into())),
+        };
+
+        producer.begin()?;
+
+        let result = self
+            .in_transaction::<T>(message, &key, producer, state)
+            .await;
+        let needs_abort = match &result {
+            Ok(outcome) => matches!(outcome, Outcome::Failed(_)),
+            Err(e) => !matches!(e, Error::ProducerFenced | Error::TransactionFatal(_)),
+        };
+        if needs_abort {
+            match producer.abort().await {
+                Err(e @ (Error::ProducerFenced | Error::TransactionFatal(_))) => return Err(e),
+                Err(e) => tracing::error!("Failed to abort transaction: {}", e),
+                Ok(()) => {}
+            }
+        }
+
+        result
+    }
+
+    async fn in_transaction<T: Transformer>(
+        &self,
+        message: &BorrowedMessage<'_>,
+        key: &str,
+        producer: &TransactionalProducer,
+        state: &T::State,
+    ) -> Result<Outcome> {
+        let records = match T::transform(&ReceivedMessage::new(message, key, None), state).await {
+            Ok(records) => records,
+            Err(e) => return Ok(Outcome::Failed(e)),
+        };
+
+        for record in records {
+            producer.send_record(record).await?;
+        }
+
+        let mut offsets = TopicPartitionList::new();
+        offsets.add_partition_offset(
+            message.topic(),
+            message.partition(),
+            Offset::Offset(message.offset() + 1),
+        )?;
+        let group_metadata = self
+            .consumer
+            .group_metadata()
+            .ok_or(Error::GroupMetadataMissing)?;
+        producer.send_offsets(offsets, group_metadata).await?;
+
+        producer.commit().await?;
+
+        Ok(Outcome::Processed)
+    }
+}
@@ -0,0 +1,44 @@
+use rdkafka::{
+    consumer::{Consumer, ConsumerContext},
+    Offset, TopicPartitionList,
+};
+
+use super::TIMEOUT;
+use crate::Result;
+
+/// Where to start reading a partition from, overriding the committed offset.
+#[derive(Debug, Clone, Copy)]
+pub enum StartPosition {
+    Beginning,
+    End,
+    Offset(i64),
+    /// First offset with a timestamp (ms since epoch) at or after the given one
+    Timestamp(i64),
+}
+
+impl StartPosition {
+    pub(crate) fn resolve<C, K>(&self, consumer: &K, topic: &str, partition: i32) -> Result<Offset>
+    where
+        C: ConsumerContext,
+        K: Consumer<C>,
+    {
+        let offset = match *self {
+            Self::Beginning => Offset::Beginning,
+            Self::End => Offset::End,
+            Self::Offset(offset) => Offset::Offset(offset),
+            Self::Timestamp(timestamp) => {
+                let mut tpl = TopicPartitionList::new();
+                tpl.add_partition_offset(topic, partition, Offset::Offset(timestamp))?;
+
+                // No message at or after the timestamp resolves to the end
+                consumer
+                    .offsets_for_times(tpl, TIMEOUT)?
+                    .find_partition(topic, partition)
+                    .map(|elem| elem.offset())
+                    .unwrap_or(Offset::End)
+            }
+        };
+
+        Ok(offset)
+    }
+}
@@ -0,0 +1,180 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::{Error, Result};
+
+/// Token bucket limit on the number of messages handed to the handler.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    pub messages_per_second: f64,
+    /// Messages allowed at once after an idle period
+    pub burst: u32,
+    pub scope: RateLimitScope,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum RateLimitScope {
+    /// One bucket for the whole consumer
+    Global,
+    /// One bucket per message key. Messages are still handled one at a time:
+    /// a throttled key holds up the messages behind it, of every key, and
+    /// only the partition of the throttled message stops being fetched.
+    PerKey,
+}
+
+impl RateLimit {
+    pub fn global(messages_per_second: f64) -> Self {
+        Self::new(messages_per_second, RateLimitScope::Global)
+    }
+
+    pub fn per_key(messages_per_second: f64) -> Self {
+        Self::new(messages_per_second, RateLimitScope::PerKey)
+    }
+
+    fn new(messages_per_second: f64, scope: RateLimitScope) -> Self {
+        Self {
+            messages_per_second,
+            burst: messages_per_second.ceil().max(1.0) as u32,
+            scope,
+        }
+    }
+
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+}
+
+pub(crate) struct RateLimiter {
+    limit: RateLimit,
+    buckets: Mutex<HashMap<String, TokenBucket>>,
+}
+
+impl RateLimiter {
+    /// Fails unless the rate is positive and finite
+    pub(crate) fn new(limit: RateLimit) -> Result<Self> {
+        if !(limit.messages_per_second > 0.0 && limit.messages_per_second.is_finite()) {
+            return Err(Error::InvalidRateLimit(limit.messages_per_second));
+        }
+
+        Ok(Self {
+            limit,
+            buckets: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub(crate) fn scope(&self) -> RateLimitScope {
+        self.limit.scope
+    }
+
+    /// Takes a token for a message with this key, returns how long to wait
+    /// before handing the message to the handler
+    pub(crate) fn reserve(&self, key: &str) -> Duration {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+        let key = match self.limit.scope {
+            RateLimitScope::Global => "",
+            RateLimitScope::PerKey => key,
+        };
+
+        // Drop refilled buckets of idle keys so they don't pile up
+        if buckets.len() > 1024 {
+            let limit = self.limit;
+            buckets.retain(|_, bucket| !bucket.is_full(&limit, now));
+        }
+
+        buckets
+            .entry(key.to_string())
+            .or_insert_with(|| TokenBucket::new(&self.limit, now))
+            .reserve(&self.limit, now)
+    }
+}
+
+struct TokenBucket {
+    /// Negative when tokens were reserved ahead of time
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(limit: &RateLimit, now: Instant) -> Self {
+        Self {
+            tokens: limit.burst as f64,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, limit: &RateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.messages_per_second).min(limit.burst as f64);
+        self.updated = now;
+    }
+
+    fn reserve(&mut self, limit: &RateLimit, now: Instant) -> Duration {
+        self.refill(limit, now);
+        self.tokens -= 1.0;
+
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            // Only out of range for rates `RateLimiter::new` rejects
+            Duration::try_from_secs_f64(-self.tokens / limit.messages_per_second)
+                .unwrap_or(Duration::ZERO)
+        }
+    }
+
+    fn is_full(&self, limit: &RateLimit, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens + elapsed * limit.messages_per_second >= limit.burst as f64
+    }
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket_burst_then_rate() {
+        let limit = RateLimit::global(10.0).with_burst(2);
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(&limit, now);
+
+        assert_eq!(bucket.reserve(&limit, now), Duration::ZERO);
+        assert_eq!(bucket.reserve(&limit, now), Duration::ZERO);
+        assert_eq!(bucket.reserve(&limit, now), Duration::from_millis(100));
+        assert_eq!(bucket.reserve(&limit, now), Duration::from_millis(200));
+
+        // 300ms later the two reserved tokens are paid back and one is free
+        let later = now + Duration::from_millis(300);
+        assert_eq!(bucket.reserve(&limit, later), Duration::ZERO);
+        assert!(!bucket.is_full(&limit, later));
+        assert!(bucket.is_full(&limit, later + Duration::from_millis(200)));
+    }
+
+    #[test]
+    fn test_rate_limiter_per_key() {
+        let limiter = RateLimiter::new(RateLimit::per_key(1.0)).unwrap();
+
+        assert_eq!(limiter.reserve("a"), Duration::ZERO);
+        assert_eq!(limiter.reserve("b"), Duration::ZERO);
+        assert!(limiter.reserve("a") > Duration::ZERO);
+    }
+
+    #[test]
+    fn test_rate_limiter_rejects_invalid_rate() {
+        for rate in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                RateLimiter::new(RateLimit::global(rate)),
+                Err(Error::InvalidRateLimit(_))
+            ));
+        }
+    }
+}
+
+// endregion: --- Tests
@@ -0,0 +1,38 @@
+use rdkafka::{consumer::BaseConsumer, TopicPartitionList};
+
+use super::KafkaContext;
+
+/// Partition lifecycle hooks, called from the rebalance callback. They run on
+/// the polling thread and should return quickly.
+#[allow(unused_variables)]
+pub trait RebalanceListener: Send + Sync {
+    /// Partitions were assigned to this consumer, e.g. to initialize caches
+    fn on_assign(&self, consumer: &BaseConsumer<KafkaContext>, partitions: &TopicPartitionList) {}
+
+    /// Partitions are about to be revoked, the last chance to flush
+    /// per-partition state and commit their offsets
+    fn on_revoke(&self, consumer: &BaseConsumer<KafkaContext>, partitions: &TopicPartitionList) {}
+
+    /// Partitions were lost (e.g. session timeout) and may already be owned by
+    /// another consumer, committing their offsets is no longer possible
+    fn on_lost(&self, partitions: &TopicPartitionList) {}
+}
+
+/// Value of `partition.assignment.strategy`
+#[derive(Debug, Clone, Copy)]
+pub enum AssignmentStrategy {
+    Range,
+    RoundRobin,
+    /// Incremental rebalancing, only revoked partitions stop being consumed
+    CooperativeSticky,
+}
+
+impl AssignmentStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Range => "range",
+            Self::RoundRobin => "roundrobin",
+            Self::CooperativeSticky => "cooperative-sticky",
+        }
+    }
+}
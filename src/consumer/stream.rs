@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use futures::Stream;
+use rdkafka::{message::BorrowedMessage, Message};
+
+use super::{commit::AckMode, Acknowledgement, InnerConsumer};
+use crate::{decode, Decode, Error, MessageHeaders, Result};
+
+/// Decoded message yielded by [`KafkaConsumer::into_stream`](super::KafkaConsumer::into_stream).
+pub struct TypedMessage<M> {
+    pub topic: String,
+    pub partition: i32,
+    pub offset: i64,
+    pub key: String,
+    /// `None` for tombstones
+    pub value: Option<M>,
+    pub headers: MessageHeaders,
+    pub ack: Acknowledgement,
+}
+
+pub(crate) fn typed_messages<M>(
+    consumer: Arc<InnerConsumer>,
+    mode: AckMode,
+) -> impl Stream<Item = Result<TypedMessage<M>>> + Send
+where
+    M: Decode<()> + Send + 'static,
+{
+    futures::stream::unfold(consumer, move |consumer| async move {
+        let item = match consumer.recv().await {
+            Ok(message) => typed_message(&consumer, &message, mode),
+            Err(e) => {
+                tracing::error!("Kafka error: {}", e);
+                Err(Error::Rdkafka(e))
+            }
+        };
+
+        Some((item, consumer))
+    })
+}
+
+fn typed_message<M: Decode<()>>(
+    consumer: &Arc<InnerConsumer>,
+    message: &BorrowedMessage<'_>,
+    mode: AckMode,
+) -> Result<TypedMessage<M>> {
+    let key = message.key().ok_or(Error::KeyMissing)?;
+    let key = decode::<String>(key)?;
+    let value = message.payload().map(decode::<M>).transpose()?;
+
+    let ack = Acknowledgement::new(
+        consumer.clone(),
+        message.topic(),
+        message.partition(),
+        message.offset(),
+        mode,
+        None,
+    );
+
+    Ok(TypedMessage {
+        topic: message.topic().to_string(),
+        partition: message.partition(),
+        offset: message.offset(),
+        key,
+        value,
+        headers: message
+            .headers()
+            .map(MessageHeaders::from)
+            .unwrap_or_default(),
+        ack,
+    })
+}
@@ -0,0 +1,55 @@
+use super::StartPosition;
+
+/// How the consumer gets its partitions.
+#[derive(Debug, Clone)]
+pub enum Subscription {
+    /// Join the consumer group and subscribe to the topics
+    Topics(Vec<String>),
+    /// Join the consumer group and subscribe to every topic matching one of
+    /// the regexes (e.g. `^orders\..*`), topics created later are picked up
+    /// on the next metadata refresh (`topic.metadata.refresh.interval.ms`)
+    Patterns(Vec<String>),
+    /// Read explicit partitions, without consumer group coordination
+    Assignment(Vec<PartitionAssignment>),
+}
+
+impl Default for Subscription {
+    fn default() -> Self {
+        Self::Topics(Vec::new())
+    }
+}
+
+impl From<Vec<String>> for Subscription {
+    fn from(topics: Vec<String>) -> Self {
+        Self::Topics(topics)
+    }
+}
+
+impl From<Vec<PartitionAssignment>> for Subscription {
+    fn from(partitions: Vec<PartitionAssignment>) -> Self {
+        Self::Assignment(partitions)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PartitionAssignment {
+    pub topic: String,
+    pub partition: i32,
+    /// `None` starts from the committed offset (or `auto.offset.reset`)
+    pub position: Option<StartPosition>,
+}
+
+impl PartitionAssignment {
+    pub fn new(topic: impl Into<String>, partition: i32) -> Self {
+        Self {
+            topic: topic.into(),
+            partition,
+            position: None,
+        }
+    }
+
+    pub fn with_position(mut self, position: StartPosition) -> Self {
+        self.position = Some(position);
+        self
+    }
+}
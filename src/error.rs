@@ -15,6 +15,31 @@ pub enum Error {
     SerializeError,
     DeserializeError,
 
+    // -- Producer
+    UnknownTopic(String),
+    BlockingTask(String),
+    /// Last error of a produce that was given up on
+    ProduceFailed {
+        attempts: u64,
+        error: Box<Error>,
+    },
+
+    // -- Transactions
+    /// Another producer with the same `transactional.id` took over
+    ProducerFenced,
+    /// The producer can't be used anymore and has to be recreated
+    TransactionFatal(rdkafka::error::KafkaError),
+    /// The current transaction has to be aborted, the producer stays usable
+    TransactionAbortable(rdkafka::error::KafkaError),
+
+    // -- Consumer
+    HandlerPanic(String),
+    HandlerTimeout(std::time::Duration),
+    /// `RateLimit::messages_per_second` must be positive and finite
+    InvalidRateLimit(f64),
+    /// Offsets can only be sent to a transaction by a consumer group member
+    GroupMetadataMissing,
+
     // -- Externals
     #[from]
     Codec(crate::codec::Error),
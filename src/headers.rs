@@ -0,0 +1,109 @@
+use rdkafka::message::{BorrowedHeaders, Header, Headers, OwnedHeaders};
+
+/// Headers of a produced or consumed message, in order. Keys may repeat.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MessageHeaders(Vec<(String, Vec<u8>)>);
+
+impl MessageHeaders {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, key: impl Into<String>, value: impl Into<Vec<u8>>) -> Self {
+        self.insert(key, value);
+        self
+    }
+
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<Vec<u8>>) {
+        self.0.push((key.into(), value.into()));
+    }
+
+    /// First value of the header
+    pub fn get(&self, key: &str) -> Option<&[u8]> {
+        self.0
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_slice())
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key)
+            .and_then(|value| std::str::from_utf8(value).ok())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[u8])> {
+        self.0
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_slice()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn to_owned_headers(&self) -> OwnedHeaders {
+        self.0.iter().fold(
+            OwnedHeaders::new_with_capacity(self.0.len()),
+            |headers, (key, value)| {
+                headers.insert(Header {
+                    key,
+                    value: Some(value),
+                })
+            },
+        )
+    }
+}
+
+impl Extend<(String, Vec<u8>)> for MessageHeaders {
+    fn extend<T: IntoIterator<Item = (String, Vec<u8>)>>(&mut self, iter: T) {
+        self.0.extend(iter);
+    }
+}
+
+impl IntoIterator for MessageHeaders {
+    type Item = (String, Vec<u8>);
+    type IntoIter = std::vec::IntoIter<(String, Vec<u8>)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl From<&BorrowedHeaders> for MessageHeaders {
+    /// Headers without a value are skipped
+    fn from(headers: &BorrowedHeaders) -> Self {
+        Self(
+            headers
+                .iter()
+                .filter_map(|header| Some((header.key.to_string(), header.value?.to_vec())))
+                .collect(),
+        )
+    }
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_headers_round_trip() {
+        let headers = MessageHeaders::new()
+            .with("correlation-id", "42")
+            .with("content-type", "application/bincode");
+
+        let owned = headers.to_owned_headers();
+        let decoded = MessageHeaders::from(owned.as_borrowed());
+
+        assert_eq!(decoded, headers);
+        assert_eq!(decoded.get_str("correlation-id"), Some("42"));
+        assert_eq!(decoded.get("missing"), None);
+    }
+}
+
+// endregion: --- Tests
@@ -9,11 +9,13 @@ pub mod service;
 mod codec;
 mod config;
 mod error;
+mod headers;
 
 // -- Flatten
 #[doc(hidden)]
 pub use async_trait;
 pub use error::{Error, Result};
+pub use headers::MessageHeaders;
 #[doc(hidden)]
 pub use rdkafka;
 
@@ -29,6 +31,28 @@ pub trait KafkaModel: Encode + Send + Sync {
     fn payload(&self) -> Result<impl Encode> {
         Ok(self)
     }
+    /// Produce a null payload instead of `payload`, deleting the key from
+    /// compacted topics
+    fn is_tombstone(&self) -> bool {
+        false
+    }
+    /// Headers such as correlation id, content type or event type
+    fn headers(&self) -> MessageHeaders {
+        MessageHeaders::new()
+    }
+    /// Raw bytes the partitioner hashes instead of the encoded key, e.g. a
+    /// payload field serialized the way other clients serialize it
+    fn partition_key(&self) -> Option<Vec<u8>> {
+        None
+    }
+    /// Explicit partition, `None` leaves it to the partitioner
+    fn partition(&self) -> Option<i32> {
+        None
+    }
+    /// Event time (ms since epoch), `None` uses the produce time
+    fn timestamp(&self) -> Option<i64> {
+        None
+    }
 }
 
 impl<K, V> KafkaModel for (K, V)
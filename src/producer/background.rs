@@ -0,0 +1,161 @@
+use std::{sync::Arc, time::Duration};
+
+use rdkafka::{
+    error::KafkaError,
+    message::DeliveryResult,
+    producer::{BaseRecord, Producer, ProducerContext, ThreadedProducer},
+    ClientContext, Message,
+};
+
+use super::{
+    hooks::RecordHooks, KafkaProducer, Partitioner, ProduceOptions, ProducerConfig,
+    ProducerInterceptor,
+};
+use crate::{KafkaModel, Result};
+
+/// Delivered or failed record, as handed to a [`DeliveryCallback`].
+#[derive(Debug)]
+pub struct Delivery<'a> {
+    pub topic: &'a str,
+    pub partition: i32,
+    /// Offset in the partition, only meaningful for successful deliveries
+    pub offset: i64,
+    pub key: Option<&'a [u8]>,
+}
+
+/// Called from the polling thread of a [`BackgroundProducer`] once librdkafka
+/// reports the outcome of a record. Must not block.
+pub trait DeliveryCallback: Send + Sync {
+    fn on_success(&self, _delivery: &Delivery<'_>) {}
+
+    fn on_failure(&self, delivery: &Delivery<'_>, error: &KafkaError) {
+        tracing::error!(
+            "Kafka delivery to {} [{}] failed: {:?}",
+            delivery.topic,
+            delivery.partition,
+            error
+        );
+    }
+}
+
+/// Only logs failures.
+pub struct LogDeliveryFailures;
+
+impl DeliveryCallback for LogDeliveryFailures {}
+
+pub struct DeliveryContext {
+    callback: Box<dyn DeliveryCallback>,
+}
+
+impl ClientContext for DeliveryContext {}
+
+impl ProducerContext for DeliveryContext {
+    type DeliveryOpaque = ();
+
+    fn delivery(&self, delivery_result: &DeliveryResult<'_>, _: Self::DeliveryOpaque) {
+        match delivery_result {
+            Ok(message) => self.callback.on_success(&Delivery {
+                topic: message.topic(),
+                partition: message.partition(),
+                offset: message.offset(),
+                key: message.key(),
+            }),
+            Err((error, message)) => self.callback.on_failure(
+                &Delivery {
+                    topic: message.topic(),
+                    partition: message.partition(),
+                    offset: message.offset(),
+                    key: message.key(),
+                },
+                error,
+            ),
+        }
+    }
+}
+
+/// Fire-and-forget producer: `send` only enqueues the record and returns,
+/// deliveries are reported to the [`DeliveryCallback`]. Records are
+/// intercepted and partitioned like with [`KafkaProducer`]. Call
+/// [`flush`](Self::flush) before shutting down, records still queued when
+/// the producer is dropped are lost.
+pub struct BackgroundProducer {
+    inner: Arc<ThreadedProducer<DeliveryContext>>,
+    hooks: RecordHooks,
+}
+
+impl BackgroundProducer {
+    pub fn create(
+        config: &ProducerConfig,
+        callback: impl DeliveryCallback + 'static,
+    ) -> Result<Self> {
+        let inner = config
+            .client_config()
+            .create_with_context(DeliveryContext {
+                callback: Box::new(callback),
+            })?;
+        Ok(Self {
+            inner: Arc::new(inner),
+            hooks: RecordHooks::default(),
+        })
+    }
+
+    pub fn inner(&self) -> &ThreadedProducer<DeliveryContext> {
+        &self.inner
+    }
+
+    /// Appends an interceptor, they run in the order they were added
+    pub fn interceptor(&mut self, interceptor: impl ProducerInterceptor + 'static) {
+        self.hooks.add_interceptor(Arc::new(interceptor));
+    }
+
+    /// See [`KafkaProducer::partitioner`]
+    pub fn partitioner(&mut self, partitioner: impl Partitioner + 'static) {
+        self.hooks.set_partitioner(Arc::new(partitioner));
+    }
+
+    /// Enqueues the record without waiting for its delivery. Only waits to
+    /// look up the partition count of a topic the partitioner didn't see yet,
+    /// fails right away only if the record can't be encoded or the local queue
+    /// is full.
+    pub async fn send(&self, topic: &str, model: &impl KafkaModel) -> Result<()> {
+        self.send_with(topic, model, &ProduceOptions::default())
+            .await
+    }
+
+    pub async fn send_with(
+        &self,
+        topic: &str,
+        model: &impl KafkaModel,
+        options: &ProduceOptions,
+    ) -> Result<()> {
+        let mut record = KafkaProducer::record(topic, model, options)?;
+        let producer = self.inner.clone();
+        self.hooks
+            .prepare(&mut record, model.partition_key(), move |topic, timeout| {
+                producer.client().fetch_metadata(Some(topic), timeout)
+            })
+            .await?;
+
+        let mut base_record = BaseRecord::to(&record.topic).key(&record.key);
+        if let Some(payload) = &record.payload {
+            base_record = base_record.payload(payload);
+        }
+        if !record.headers.is_empty() {
+            base_record = base_record.headers(record.headers.to_owned_headers());
+        }
+        if let Some(partition) = record.partition {
+            base_record = base_record.partition(partition);
+        }
+        if let Some(timestamp) = record.timestamp {
+            base_record = base_record.timestamp(timestamp);
+        }
+
+        self.inner.send(base_record).map_err(|(e, _)| e.into())
+    }
+
+    /// Waits until every enqueued record was delivered or failed
+    pub fn flush(&self, timeout: Duration) -> Result<()> {
+        self.inner.flush(timeout)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,130 @@
+use std::time::Duration;
+
+use crate::config::kafka_config;
+
+/// Broker acknowledgements a record needs to count as delivered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Acks {
+    None,
+    Leader,
+    /// Every in-sync replica, required by idempotence
+    All,
+}
+
+impl Acks {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "0",
+            Self::Leader => "1",
+            Self::All => "all",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Snappy,
+    Lz4,
+    Zstd,
+}
+
+impl Compression {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Gzip => "gzip",
+            Self::Snappy => "snappy",
+            Self::Lz4 => "lz4",
+            Self::Zstd => "zstd",
+        }
+    }
+}
+
+/// Producer settings. The defaults favour safety: idempotent writes
+/// acknowledged by every in-sync replica, so librdkafka retries neither
+/// duplicate nor reorder records.
+#[derive(Debug, Clone)]
+pub struct ProducerConfig {
+    pub uri: String,
+    pub acks: Acks,
+    /// Needs `acks` set to [`Acks::All`] and at most 5 requests in flight
+    pub idempotence: bool,
+    /// How long records wait to be batched with others
+    pub linger: Duration,
+    /// Maximum batch size in bytes
+    pub batch_size: usize,
+    pub compression: Compression,
+    /// Maximum unacknowledged requests per broker connection
+    pub max_in_flight: usize,
+    /// Time a record may spend waiting, including retries, before its
+    /// delivery fails. `None` keeps the librdkafka default, which transactional
+    /// producers cap to `transaction.timeout.ms`; a value above it fails their
+    /// creation.
+    pub message_timeout: Option<Duration>,
+}
+
+impl ProducerConfig {
+    pub fn new(uri: impl Into<String>) -> Self {
+        Self {
+            uri: uri.into(),
+            acks: Acks::All,
+            idempotence: true,
+            linger: Duration::from_millis(5),
+            batch_size: 1_000_000,
+            compression: Compression::None,
+            max_in_flight: 5,
+            message_timeout: None,
+        }
+    }
+
+    pub(crate) fn client_config(&self) -> rdkafka::ClientConfig {
+        let mut config = rdkafka::ClientConfig::new();
+        config
+            .set("bootstrap.servers", &self.uri)
+            .set("acks", self.acks.as_str())
+            .set("enable.idempotence", self.idempotence.to_string())
+            .set("linger.ms", self.linger.as_millis().to_string())
+            .set("batch.size", self.batch_size.to_string())
+            .set("compression.type", self.compression.as_str())
+            // Same partitions as the Java client for keyed records
+            .set("partitioner", "murmur2_random")
+            .set(
+                "max.in.flight.requests.per.connection",
+                self.max_in_flight.to_string(),
+            );
+        if let Some(timeout) = self.message_timeout {
+            config.set("message.timeout.ms", timeout.as_millis().to_string());
+        }
+        config
+    }
+}
+
+impl Default for ProducerConfig {
+    fn default() -> Self {
+        Self::new(kafka_config().KAFKA_URI.clone())
+    }
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_producer_config_safe_defaults() {
+        let config = ProducerConfig::new("localhost:9092").client_config();
+
+        assert_eq!(config.get("enable.idempotence"), Some("true"));
+        assert_eq!(config.get("acks"), Some("all"));
+        assert_eq!(config.get("partitioner"), Some("murmur2_random"));
+        assert_eq!(
+            config.get("max.in.flight.requests.per.connection"),
+            Some("5")
+        );
+    }
+}
+
+// endregion: --- Tests
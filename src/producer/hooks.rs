@@ -0,0 +1,177 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use rdkafka::{error::KafkaResult, metadata::Metadata};
+
+use super::{Murmur2Partitioner, Partitioner, ProducerInterceptor, ProducerRecord};
+use crate::{kafka_config, Error, Result};
+
+/// How long a fetched partition count is trusted, so added partitions are
+/// picked up by the partitioner
+const PARTITION_COUNT_TTL: Duration = Duration::from_secs(300);
+
+/// Interceptors and partitioning every producer runs a record through
+/// before handing it to librdkafka.
+#[derive(Default)]
+pub(crate) struct RecordHooks {
+    interceptors: Vec<Arc<dyn ProducerInterceptor>>,
+    partitioner: Option<Arc<dyn Partitioner>>,
+    partition_counts: Mutex<HashMap<String, (i32, Instant)>>,
+}
+
+impl RecordHooks {
+    pub(crate) fn add_interceptor(&mut self, interceptor: Arc<dyn ProducerInterceptor>) {
+        self.interceptors.push(interceptor);
+    }
+
+    pub(crate) fn set_partitioner(&mut self, partitioner: Arc<dyn Partitioner>) {
+        self.partitioner = Some(partitioner);
+    }
+
+    /// Runs the interceptors, then partitions the record they produced (as the
+    /// Java client does), so rewritten keys and topics are partitioned right.
+    /// `fetch_metadata` is only called, off the async runtime, when the
+    /// partition count of the topic isn't cached.
+    pub(crate) async fn prepare(
+        &self,
+        record: &mut ProducerRecord,
+        partition_key: Option<Vec<u8>>,
+        fetch_metadata: impl FnOnce(&str, Duration) -> KafkaResult<Metadata> + Send + 'static,
+    ) -> Result<()> {
+        self.intercept(record)?;
+        self.assign_partition(record, partition_key, fetch_metadata)
+            .await
+    }
+
+    fn intercept(&self, record: &mut ProducerRecord) -> Result<()> {
+        for interceptor in &self.interceptors {
+            interceptor.on_send(record)?;
+        }
+
+        Ok(())
+    }
+
+    async fn assign_partition(
+        &self,
+        record: &mut ProducerRecord,
+        partition_key: Option<Vec<u8>>,
+        fetch_metadata: impl FnOnce(&str, Duration) -> KafkaResult<Metadata> + Send + 'static,
+    ) -> Result<()> {
+        if record.partition.is_some() {
+            return Ok(());
+        }
+
+        let partitioner: &dyn Partitioner = match (&self.partitioner, &partition_key) {
+            (Some(partitioner), _) => partitioner.as_ref(),
+            (None, Some(_)) => &Murmur2Partitioner,
+            (None, None) => return Ok(()),
+        };
+
+        let partition_count = self.partition_count(&record.topic, fetch_metadata).await?;
+        let key = partition_key.as_deref().unwrap_or(&record.key);
+        record.partition = Some(partitioner.partition(&record.topic, key, partition_count));
+
+        Ok(())
+    }
+
+    async fn partition_count(
+        &self,
+        topic: &str,
+        fetch_metadata: impl FnOnce(&str, Duration) -> KafkaResult<Metadata> + Send + 'static,
+    ) -> Result<i32> {
+        let cached = self
+            .partition_counts
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(topic)
+            .filter(|(_, fetched)| fetched.elapsed() < PARTITION_COUNT_TTL)
+            .map(|(count, _)| *count);
+        if let Some(count) = cached {
+            return Ok(count);
+        }
+
+        let topic_name = topic.to_string();
+        let metadata = tokio::task::spawn_blocking(move || {
+            fetch_metadata(
+                &topic_name,
+                Duration::from_millis(kafka_config().KAFKA_PRODUCE_TIMEOUT_MS),
+            )
+        })
+        .await
+        .map_err(|e| Error::BlockingTask(e.to_string()))??;
+
+        let count = metadata
+            .topics()
+            .iter()
+            .find(|t| t.name() == topic)
+            .map(|t| t.partitions().len() as i32)
+            .filter(|count| *count > 0)
+            .ok_or_else(|| Error::UnknownTopic(topic.to_string()))?;
+
+        self.partition_counts
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(topic.to_string(), (count, Instant::now()));
+
+        Ok(count)
+    }
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MessageHeaders;
+
+    struct FirstBytePartitioner;
+
+    impl Partitioner for FirstBytePartitioner {
+        fn partition(&self, _topic: &str, key: &[u8], partition_count: i32) -> i32 {
+            key[0] as i32 % partition_count
+        }
+    }
+
+    struct RewriteKey;
+
+    impl ProducerInterceptor for RewriteKey {
+        fn on_send(&self, record: &mut ProducerRecord) -> Result<()> {
+            record.key = vec![3];
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prepare_partitions_intercepted_record() {
+        let mut hooks = RecordHooks::default();
+        hooks.add_interceptor(Arc::new(RewriteKey));
+        hooks.set_partitioner(Arc::new(FirstBytePartitioner));
+        hooks
+            .partition_counts
+            .lock()
+            .unwrap()
+            .insert("topic".to_string(), (10, Instant::now()));
+
+        let mut record = ProducerRecord {
+            topic: "topic".to_string(),
+            key: vec![1],
+            payload: None,
+            headers: MessageHeaders::new(),
+            partition: None,
+            timestamp: None,
+        };
+        hooks
+            .prepare(&mut record, None, |_, _| {
+                unreachable!("partition count is cached")
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(record.partition, Some(3));
+    }
+}
+
+// endregion: --- Tests
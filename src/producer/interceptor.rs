@@ -0,0 +1,8 @@
+use super::ProducerRecord;
+use crate::Result;
+
+/// Called for every record before it is handed to librdkafka, can mutate it
+/// or reject it by returning an error.
+pub trait ProducerInterceptor: Send + Sync {
+    fn on_send(&self, record: &mut ProducerRecord) -> Result<()>;
+}
@@ -0,0 +1,326 @@
+mod background;
+mod config;
+mod hooks;
+mod interceptor;
+mod options;
+mod partitioner;
+mod record;
+mod retry;
+mod transaction;
+
+// region:    --- Modules
+
+use crate::{encode, kafka_config, Encode, Error, KafkaModel, MessageHeaders, Result};
+use async_trait::async_trait;
+use hooks::RecordHooks;
+use rdkafka::{
+    producer::{FutureProducer, Producer},
+    util::Timeout,
+};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+pub use background::{
+    BackgroundProducer, Delivery, DeliveryCallback, DeliveryContext, LogDeliveryFailures,
+};
+pub use config::{Acks, Compression, ProducerConfig};
+pub use interceptor::ProducerInterceptor;
+pub use options::ProduceOptions;
+pub use partitioner::{murmur2, Murmur2Partitioner, Partitioner};
+pub use record::ProducerRecord;
+pub use retry::{is_retriable, RetryPolicy};
+pub use transaction::TransactionalProducer;
+
+// endregion: --- Modules
+
+#[async_trait]
+pub trait ProducerLike: Send + Sync {
+    async fn produce(&self, topic: &str, model: &impl KafkaModel) -> Result<()>;
+    async fn produce_with(
+        &self,
+        topic: &str,
+        model: &impl KafkaModel,
+        options: &ProduceOptions,
+    ) -> Result<()>;
+    /// Retries transient errors, also of the partition count lookup. Every
+    /// failure, retried or not, is returned as [`Error::ProduceFailed`].
+    async fn produce_with_retries(
+        &self,
+        topic: &str,
+        model: &impl KafkaModel,
+        policy: &RetryPolicy,
+    ) -> Result<()>;
+    /// Enqueues every model before awaiting the deliveries concurrently, the
+    /// results are in the order of `models`
+    async fn produce_many<'m, M: KafkaModel + 'm>(
+        &self,
+        topic: &str,
+        models: impl IntoIterator<Item = &'m M> + Send,
+    ) -> Vec<Result<()>>;
+    /// Produces already encoded bytes, e.g. records relayed from another topic
+    async fn produce_raw(
+        &self,
+        topic: &str,
+        key: impl Into<Vec<u8>> + Send,
+        payload: impl Into<Vec<u8>> + Send,
+    ) -> Result<()>;
+    /// Produces a record with a null payload, deleting the key from compacted topics
+    async fn produce_tombstone(&self, topic: &str, key: &(impl Encode + Sync)) -> Result<()>;
+}
+
+pub struct KafkaProducer {
+    inner: FutureProducer,
+    hooks: RecordHooks,
+}
+
+impl KafkaProducer {
+    pub fn new(producer: FutureProducer) -> Self {
+        Self {
+            inner: producer,
+            hooks: RecordHooks::default(),
+        }
+    }
+
+    pub fn create(config: &ProducerConfig) -> Result<Self> {
+        let producer: FutureProducer = config.client_config().create()?;
+        Ok(Self::new(producer))
+    }
+
+    pub fn inner(&self) -> &FutureProducer {
+        &self.inner
+    }
+
+    /// Appends an interceptor, they run in the order they were added
+    pub fn interceptor(&mut self, interceptor: impl ProducerInterceptor + 'static) {
+        self.hooks.add_interceptor(Arc::new(interceptor));
+    }
+
+    /// Partitions every record without an explicit partition. Without one,
+    /// models with a [`KafkaModel::partition_key`] are partitioned with
+    /// [`Murmur2Partitioner`] and the rest by librdkafka, with the same murmur2
+    /// when created from a [`ProducerConfig`].
+    pub fn partitioner(&mut self, partitioner: impl Partitioner + 'static) {
+        self.hooks.set_partitioner(Arc::new(partitioner));
+    }
+
+    /// Intercepts and partitions the record
+    async fn prepare(
+        &self,
+        record: &mut ProducerRecord,
+        partition_key: Option<Vec<u8>>,
+    ) -> Result<()> {
+        let producer = self.inner.clone();
+        self.hooks
+            .prepare(record, partition_key, move |topic, timeout| {
+                producer.client().fetch_metadata(Some(topic), timeout)
+            })
+            .await
+    }
+
+    /// Intercepts, partitions and sends an already encoded record
+    pub(crate) async fn send_record(&self, mut record: ProducerRecord) -> Result<()> {
+        self.prepare(&mut record, None).await?;
+        self.deliver(&record).await
+    }
+
+    /// Encodes, intercepts and partitions the model
+    async fn prepared_record(
+        &self,
+        topic: &str,
+        model: &impl KafkaModel,
+        options: &ProduceOptions,
+    ) -> Result<ProducerRecord> {
+        let mut record = Self::record(topic, model, options)?;
+        self.prepare(&mut record, model.partition_key()).await?;
+        Ok(record)
+    }
+
+    fn record(
+        topic: &str,
+        model: &impl KafkaModel,
+        options: &ProduceOptions,
+    ) -> Result<ProducerRecord> {
+        let payload = if model.is_tombstone() {
+            None
+        } else {
+            Some(encode(&model.payload()?)?)
+        };
+
+        let mut headers = model.headers();
+        headers.extend(options.headers.clone());
+
+        Ok(ProducerRecord {
+            topic: topic.to_string(),
+            key: encode(&model.key())?,
+            payload,
+            headers,
+            partition: options.partition.or(model.partition()),
+            timestamp: options.timestamp.or(model.timestamp()),
+        })
+    }
+
+    /// Sends the record as is, it's only borrowed so retries can send it again
+    async fn deliver(&self, record: &ProducerRecord) -> Result<()> {
+        let start = std::time::Instant::now();
+
+        let mut future_record = rdkafka::producer::FutureRecord::to(&record.topic).key(&record.key);
+        if let Some(payload) = &record.payload {
+            future_record = future_record.payload(payload);
+        }
+        if !record.headers.is_empty() {
+            future_record = future_record.headers(record.headers.to_owned_headers());
+        }
+        if let Some(partition) = record.partition {
+            future_record = future_record.partition(partition);
+        }
+        if let Some(timestamp) = record.timestamp {
+            future_record = future_record.timestamp(timestamp);
+        }
+
+        let status_delivery = self
+            .inner
+            .send(
+                future_record,
+                Timeout::After(Duration::from_millis(
+                    kafka_config().KAFKA_PRODUCE_TIMEOUT_MS,
+                )),
+            )
+            .await;
+
+        match status_delivery {
+            Ok(report) => {
+                let duration = start.elapsed();
+                tracing::debug!("Kafka send successful: {:?}, took {:?}", report, duration);
+                Ok(())
+            }
+            Err((e, _)) => {
+                tracing::error!("Kafka send failed: {:?}", e);
+                Err(crate::Error::Rdkafka(e))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl ProducerLike for KafkaProducer {
+    async fn produce(&self, topic: &str, model: &impl KafkaModel) -> Result<()> {
+        self.produce_with(topic, model, &ProduceOptions::default())
+            .await
+    }
+
+    async fn produce_with(
+        &self,
+        topic: &str,
+        model: &impl KafkaModel,
+        options: &ProduceOptions,
+    ) -> Result<()> {
+        let record = self.prepared_record(topic, model, options).await?;
+        self.deliver(&record).await
+    }
+
+    async fn produce_with_retries(
+        &self,
+        topic: &str,
+        model: &impl KafkaModel,
+        policy: &RetryPolicy,
+    ) -> Result<()> {
+        let start = Instant::now();
+        let mut retry = 0;
+        // Encoded, intercepted and partitioned once it first succeeds
+        let mut record = None;
+        loop {
+            let result = match &record {
+                Some(record) => self.deliver(record).await,
+                None => {
+                    match self
+                        .prepared_record(topic, model, &ProduceOptions::default())
+                        .await
+                    {
+                        Ok(prepared) => self.deliver(record.insert(prepared)).await,
+                        Err(e) => Err(e),
+                    }
+                }
+            };
+            let error = match result {
+                Ok(()) => return Ok(()),
+                Err(e) => e,
+            };
+
+            let backoff = policy.jittered_backoff(retry);
+            let give_up = retry >= policy.max_retries
+                || !is_retriable(&error)
+                || policy
+                    .max_elapsed
+                    .is_some_and(|max| start.elapsed() + backoff > max);
+            if give_up {
+                return Err(Error::ProduceFailed {
+                    attempts: retry + 1,
+                    error: Box::new(error),
+                });
+            }
+
+            tracing::warn!(
+                "Produce attempt {} failed: {}, retrying in {:?}...",
+                retry + 1,
+                error,
+                backoff
+            );
+            tokio::time::sleep(backoff).await;
+            retry += 1;
+        }
+    }
+
+    async fn produce_many<'m, M: KafkaModel + 'm>(
+        &self,
+        topic: &str,
+        models: impl IntoIterator<Item = &'m M> + Send,
+    ) -> Vec<Result<()>> {
+        let models: Vec<&M> = models.into_iter().collect();
+        let mut records = Vec::with_capacity(models.len());
+        for model in models {
+            records.push(
+                self.prepared_record(topic, model, &ProduceOptions::default())
+                    .await,
+            );
+        }
+
+        futures::future::join_all(records.into_iter().map(|record| async move {
+            match record {
+                Ok(record) => self.deliver(&record).await,
+                Err(e) => Err(e),
+            }
+        }))
+        .await
+    }
+
+    async fn produce_raw(
+        &self,
+        topic: &str,
+        key: impl Into<Vec<u8>> + Send,
+        payload: impl Into<Vec<u8>> + Send,
+    ) -> Result<()> {
+        let record = ProducerRecord {
+            topic: topic.to_string(),
+            key: key.into(),
+            payload: Some(payload.into()),
+            headers: MessageHeaders::new(),
+            partition: None,
+            timestamp: None,
+        };
+        self.send_record(record).await
+    }
+
+    async fn produce_tombstone(&self, topic: &str, key: &(impl Encode + Sync)) -> Result<()> {
+        let record = ProducerRecord {
+            topic: topic.to_string(),
+            key: encode(key)?,
+            payload: None,
+            headers: MessageHeaders::new(),
+            partition: None,
+            timestamp: None,
+        };
+        self.send_record(record).await
+    }
+}
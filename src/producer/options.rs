@@ -0,0 +1,33 @@
+use crate::MessageHeaders;
+
+/// Per call produce settings, on top of what the model declares.
+#[derive(Debug, Clone, Default)]
+pub struct ProduceOptions {
+    /// Added after the headers of the model
+    pub headers: MessageHeaders,
+    /// Overrides the partition of the model
+    pub partition: Option<i32>,
+    /// Event time (ms since epoch), overrides the timestamp of the model
+    pub timestamp: Option<i64>,
+}
+
+impl ProduceOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn header(mut self, key: impl Into<String>, value: impl Into<Vec<u8>>) -> Self {
+        self.headers.insert(key, value);
+        self
+    }
+
+    pub fn partition(mut self, partition: i32) -> Self {
+        self.partition = Some(partition);
+        self
+    }
+
+    pub fn timestamp(mut self, timestamp: i64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+}
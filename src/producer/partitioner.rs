@@ -0,0 +1,92 @@
+/// Picks the partition of a record from its partition key: the bytes returned
+/// by [`KafkaModel::partition_key`](crate::KafkaModel::partition_key), or the
+/// encoded key.
+pub trait Partitioner: Send + Sync {
+    fn partition(&self, topic: &str, key: &[u8], partition_count: i32) -> i32;
+}
+
+/// Partitioner of the Java client for keyed records, records with the same key
+/// bytes land in the same partition as records produced by Java clients.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Murmur2Partitioner;
+
+impl Partitioner for Murmur2Partitioner {
+    fn partition(&self, _topic: &str, key: &[u8], partition_count: i32) -> i32 {
+        (murmur2(key) & 0x7fff_ffff) % partition_count
+    }
+}
+
+/// 32-bit murmur2 with the seed used by the Java client
+pub fn murmur2(data: &[u8]) -> i32 {
+    const SEED: u32 = 0x9747_b28c;
+    const M: u32 = 0x5bd1_e995;
+    const R: u32 = 24;
+
+    let mut h = SEED ^ data.len() as u32;
+
+    let mut chunks = data.chunks_exact(4);
+    for chunk in &mut chunks {
+        let mut k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+
+        h = h.wrapping_mul(M);
+        h ^= k;
+    }
+
+    let tail = chunks.remainder();
+    if tail.len() >= 3 {
+        h ^= (tail[2] as u32) << 16;
+    }
+    if tail.len() >= 2 {
+        h ^= (tail[1] as u32) << 8;
+    }
+    if !tail.is_empty() {
+        h ^= tail[0] as u32;
+        h = h.wrapping_mul(M);
+    }
+
+    h ^= h >> 13;
+    h = h.wrapping_mul(M);
+    h ^= h >> 15;
+
+    h as i32
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_murmur2_matches_java_client() {
+        // Expected values from the Java client's own tests
+        let cases: [(&str, i32); 6] = [
+            ("21", -973932308),
+            ("foobar", -790332482),
+            ("a-little-bit-long-string", -985981536),
+            ("a-little-bit-longer-string", -1486304829),
+            (
+                "lkjh234lh9fiuh90y23oiuhsafujhadof229phr9h19h89h8",
+                -58897971,
+            ),
+            ("abc", 479470107),
+        ];
+
+        for (key, expected) in cases {
+            assert_eq!(murmur2(key.as_bytes()), expected, "key: {key}");
+        }
+    }
+
+    #[test]
+    fn test_murmur2_partitioner_in_range() {
+        for key in ["21", "foobar", "abc", ""] {
+            let partition = Murmur2Partitioner.partition("topic", key.as_bytes(), 12);
+            assert!((0..12).contains(&partition));
+        }
+    }
+}
+
+// endregion: --- Tests
@@ -0,0 +1,22 @@
+use super::{KafkaProducer, ProduceOptions};
+use crate::{KafkaModel, MessageHeaders, Result};
+
+/// Encoded record about to be produced.
+#[derive(Debug, Clone)]
+pub struct ProducerRecord {
+    pub topic: String,
+    pub key: Vec<u8>,
+    /// `None` for tombstones
+    pub payload: Option<Vec<u8>>,
+    pub headers: MessageHeaders,
+    /// `None` leaves the choice to the partitioner
+    pub partition: Option<i32>,
+    /// `None` uses the produce time
+    pub timestamp: Option<i64>,
+}
+
+impl ProducerRecord {
+    pub fn from_model(topic: &str, model: &impl KafkaModel) -> Result<Self> {
+        KafkaProducer::record(topic, model, &ProduceOptions::default())
+    }
+}
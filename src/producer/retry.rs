@@ -0,0 +1,155 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+
+use crate::Error;
+
+/// Exponential backoff between produce attempts, only for transient errors.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt
+    pub max_retries: u64,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Backoff growth factor per retry, at least 1.0
+    pub multiplier: f64,
+    /// Fraction of the backoff (clamped to 0.0..=1.0) randomly taken off, so
+    /// producers failing together don't retry together
+    pub jitter: f64,
+    /// Gives up once the next retry would start later than this after the
+    /// first attempt
+    pub max_elapsed: Option<Duration>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            multiplier: 2.0,
+            jitter: 0.2,
+            max_elapsed: Some(Duration::from_secs(30)),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Backoff before retry number `retry` (starting at 0), before jitter
+    pub fn backoff(&self, retry: u64) -> Duration {
+        // `f64::max` also replaces NaN
+        let factor = self
+            .multiplier
+            .max(1.0)
+            .powi(retry.min(i32::MAX as u64) as i32);
+        // Capped before converting back, a large factor overflows `Duration`
+        let secs =
+            (self.initial_backoff.as_secs_f64() * factor).min(self.max_backoff.as_secs_f64());
+        Duration::try_from_secs_f64(secs).unwrap_or(self.max_backoff)
+    }
+
+    pub(crate) fn jittered_backoff(&self, retry: u64) -> Duration {
+        let jitter = if self.jitter.is_nan() {
+            0.0
+        } else {
+            self.jitter.clamp(0.0, 1.0) * random_fraction()
+        };
+        self.backoff(retry).mul_f64(1.0 - jitter)
+    }
+}
+
+/// Whether producing again may succeed: timeouts, full queues, unreachable
+/// brokers and leader changes
+pub fn is_retriable(error: &Error) -> bool {
+    let Error::Rdkafka(error) = error else {
+        return false;
+    };
+
+    if let KafkaError::Transaction(e) = error {
+        return e.is_retriable();
+    }
+
+    matches!(
+        error.rdkafka_error_code(),
+        Some(
+            RDKafkaErrorCode::QueueFull
+                | RDKafkaErrorCode::MessageTimedOut
+                | RDKafkaErrorCode::RequestTimedOut
+                | RDKafkaErrorCode::OperationTimedOut
+                | RDKafkaErrorCode::BrokerTransportFailure
+                | RDKafkaErrorCode::AllBrokersDown
+                | RDKafkaErrorCode::BrokerNotAvailable
+                | RDKafkaErrorCode::NetworkException
+                | RDKafkaErrorCode::LeaderNotAvailable
+                | RDKafkaErrorCode::NotLeaderForPartition
+                | RDKafkaErrorCode::NotEnoughReplicas
+                | RDKafkaErrorCode::NotEnoughReplicasAfterAppend
+                | RDKafkaErrorCode::KafkaStorageError
+        )
+    )
+}
+
+/// Uniform in `0.0..1.0`, from the randomly keyed std hasher
+fn random_fraction() -> f64 {
+    let bits = RandomState::new().build_hasher().finish() >> 11;
+    bits as f64 / (1u64 << 53) as f64
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_policy_backoff() {
+        let policy = RetryPolicy::default();
+
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(400));
+        assert_eq!(policy.backoff(20), Duration::from_secs(5));
+        assert_eq!(policy.backoff(u64::MAX), Duration::from_secs(5));
+
+        let jittered = policy.jittered_backoff(2);
+        assert!(jittered <= Duration::from_millis(400));
+        assert!(jittered >= Duration::from_millis(320));
+    }
+
+    #[test]
+    fn test_retry_policy_out_of_range_values() {
+        let policy = RetryPolicy {
+            multiplier: -2.0,
+            jitter: f64::NAN,
+            ..RetryPolicy::default()
+        };
+        assert_eq!(policy.backoff(3), Duration::from_millis(100));
+        assert_eq!(policy.jittered_backoff(3), Duration::from_millis(100));
+
+        let policy = RetryPolicy {
+            initial_backoff: Duration::ZERO,
+            multiplier: f64::INFINITY,
+            jitter: -1.0,
+            ..RetryPolicy::default()
+        };
+        assert_eq!(policy.jittered_backoff(0), Duration::ZERO);
+        assert_eq!(policy.jittered_backoff(1), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_is_retriable() {
+        let queue_full = Error::Rdkafka(KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull));
+        let too_large = Error::Rdkafka(KafkaError::MessageProduction(
+            RDKafkaErrorCode::MessageSizeTooLarge,
+        ));
+
+        assert!(is_retriable(&queue_full));
+        assert!(!is_retriable(&too_large));
+        assert!(!is_retriable(&Error::SerializeError));
+    }
+}
+
+// endregion: --- Tests
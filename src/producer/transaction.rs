@@ -0,0 +1,195 @@
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use rdkafka::{
+    consumer::ConsumerGroupMetadata,
+    error::{KafkaError, KafkaResult, RDKafkaErrorCode},
+    producer::{FutureProducer, Producer},
+    TopicPartitionList,
+};
+
+use super::{KafkaProducer, ProduceOptions, ProducerConfig, ProducerLike, ProducerRecord};
+use crate::{Error, KafkaModel, Result};
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Producer whose records only become visible to `read_committed` consumers
+/// once the transaction they were sent in is committed, across topics.
+///
+/// Only one transaction can be open at a time per producer.
+pub struct TransactionalProducer {
+    producer: KafkaProducer,
+}
+
+impl TransactionalProducer {
+    /// Creates the producer and initializes transactions, fencing off any
+    /// previous producer with the same `transactional_id`. Blocks until the
+    /// transaction coordinator answered.
+    pub fn create(config: &ProducerConfig, transactional_id: &str) -> Result<Self> {
+        let producer: FutureProducer = client_config(config, transactional_id).create()?;
+        producer
+            .init_transactions(TIMEOUT)
+            .map_err(transaction_error)?;
+
+        Ok(Self {
+            producer: KafkaProducer::new(producer),
+        })
+    }
+
+    /// Underlying producer, e.g. to add interceptors or a partitioner
+    pub fn producer(&mut self) -> &mut KafkaProducer {
+        &mut self.producer
+    }
+
+    pub fn begin(&self) -> Result<()> {
+        self.producer
+            .inner()
+            .begin_transaction()
+            .map_err(transaction_error)
+    }
+
+    pub async fn send(&self, topic: &str, model: &impl KafkaModel) -> Result<()> {
+        self.send_with(topic, model, &ProduceOptions::default())
+            .await
+    }
+
+    pub async fn send_with(
+        &self,
+        topic: &str,
+        model: &impl KafkaModel,
+        options: &ProduceOptions,
+    ) -> Result<()> {
+        self.producer
+            .produce_with(topic, model, options)
+            .await
+            .map_err(|e| match e {
+                Error::Rdkafka(e) => transaction_error(e),
+                e => e,
+            })
+    }
+
+    pub async fn send_record(&self, record: ProducerRecord) -> Result<()> {
+        self.producer
+            .send_record(record)
+            .await
+            .map_err(|e| match e {
+                Error::Rdkafka(e) => transaction_error(e),
+                e => e,
+            })
+    }
+
+    /// Commits the consumer offsets (of the next messages to read) as part of
+    /// the transaction, instead of through the consumer
+    pub async fn send_offsets(
+        &self,
+        offsets: TopicPartitionList,
+        group_metadata: ConsumerGroupMetadata,
+    ) -> Result<()> {
+        self.blocking(move |producer| {
+            producer.send_offsets_to_transaction(&offsets, &group_metadata, TIMEOUT)
+        })
+        .await
+    }
+
+    /// Flushes the records of the transaction and commits it
+    pub async fn commit(&self) -> Result<()> {
+        self.blocking(|producer| producer.commit_transaction(TIMEOUT))
+            .await
+    }
+
+    /// Drops every record sent in the transaction
+    pub async fn abort(&self) -> Result<()> {
+        self.blocking(|producer| producer.abort_transaction(TIMEOUT))
+            .await
+    }
+
+    /// Runs `f` in a transaction, committed when `f` succeeds and aborted
+    /// when `f` or the commit fails.
+    pub async fn transaction<T, F>(&self, f: F) -> Result<T>
+    where
+        F: for<'a> FnOnce(&'a Self) -> BoxFuture<'a, Result<T>>,
+    {
+        self.begin()?;
+
+        let result = match f(self).await {
+            Ok(value) => self.commit().await.map(|()| value),
+            Err(e) => Err(e),
+        };
+
+        if let Err(e) = &result {
+            if !matches!(e, Error::ProducerFenced | Error::TransactionFatal(_)) {
+                if let Err(abort_error) = self.abort().await {
+                    tracing::error!("Failed to abort transaction: {:?}", abort_error);
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Runs a blocking transaction call off the async runtime
+    async fn blocking(
+        &self,
+        f: impl FnOnce(&FutureProducer) -> KafkaResult<()> + Send + 'static,
+    ) -> Result<()> {
+        let producer = self.producer.inner().clone();
+        tokio::task::spawn_blocking(move || f(&producer))
+            .await
+            .map_err(|e| Error::BlockingTask(e.to_string()))?
+            .map_err(transaction_error)
+    }
+}
+
+fn client_config(config: &ProducerConfig, transactional_id: &str) -> rdkafka::ClientConfig {
+    let mut client_config = config.client_config();
+    client_config.set("transactional.id", transactional_id);
+    client_config
+}
+
+/// Separates fencing and fatal errors, after which the producer is unusable,
+/// from errors that only require aborting the current transaction
+pub(crate) fn transaction_error(error: KafkaError) -> Error {
+    if matches!(
+        error.rdkafka_error_code(),
+        Some(
+            RDKafkaErrorCode::Fenced
+                | RDKafkaErrorCode::ProducerFenced
+                | RDKafkaErrorCode::InvalidProducerEpoch
+        )
+    ) {
+        return Error::ProducerFenced;
+    }
+
+    match &error {
+        KafkaError::Transaction(e) if e.is_fatal() => Error::TransactionFatal(error),
+        KafkaError::Transaction(e) if e.txn_requires_abort() => Error::TransactionAbortable(error),
+        _ => Error::Rdkafka(error),
+    }
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transaction_error_fenced() {
+        let error = KafkaError::MessageProduction(RDKafkaErrorCode::ProducerFenced);
+        assert!(matches!(transaction_error(error), Error::ProducerFenced));
+
+        let error = KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull);
+        assert!(matches!(transaction_error(error), Error::Rdkafka(_)));
+    }
+
+    #[test]
+    fn test_transactional_client_config_creates() {
+        // Creating the client validates the config without reaching a broker
+        let config = client_config(&ProducerConfig::new("localhost:9092"), "tx");
+        let producer = config.create::<FutureProducer>();
+
+        assert!(producer.is_ok(), "{:?}", producer.err());
+    }
+}
+
+// endregion: --- Tests
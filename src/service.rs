@@ -1,9 +1,12 @@
 use crate::{
-    consumer::{KafkaConsumer, StateReceiver},
+    consumer::{ConsumerHandle, KafkaConsumer, PartitionLag, StateReceiver, Transformer},
     dummy::{DummyReceiver, DummyState},
     kafka_config,
-    producer::{KafkaProducer, ProducerLike},
-    KafkaModel, Result,
+    producer::{
+        KafkaProducer, ProduceOptions, ProducerConfig, ProducerLike, RetryPolicy,
+        TransactionalProducer,
+    },
+    Encode, KafkaModel, Result,
 };
 use std::sync::Arc;
 
@@ -12,6 +15,7 @@ where
     R: StateReceiver + Send + Sync,
 {
     consumer: Option<KafkaConsumer>,
+    consumer_handle: Option<ConsumerHandle>,
     producer: Arc<KafkaProducer>,
     receiver: std::marker::PhantomData<R>,
     state: Arc<R::State>,
@@ -22,10 +26,11 @@ where
 impl KafkaService<DummyReceiver> {
     pub fn with_dummy_state(kafka_uri: &str) -> Result<Arc<Self>> {
         let state = Arc::new(DummyState);
-        let producer = KafkaProducer::create(kafka_uri)?;
+        let producer = KafkaProducer::create(&ProducerConfig::new(kafka_uri))?;
 
         Ok(Arc::new(Self {
             consumer: None,
+            consumer_handle: None,
             producer: Arc::new(producer),
             receiver: std::marker::PhantomData,
             state,
@@ -49,6 +54,7 @@ where
         state: Arc<R::State>,
     ) -> Self {
         Self {
+            consumer_handle: consumer.as_ref().map(KafkaConsumer::handle),
             consumer,
             producer: Arc::new(producer),
             receiver: std::marker::PhantomData,
@@ -57,10 +63,11 @@ where
     }
 
     pub fn producer_only(kafka_uri: &str, state: Arc<R::State>) -> Result<Arc<Self>> {
-        let producer = KafkaProducer::create(kafka_uri)?;
+        let producer = KafkaProducer::create(&ProducerConfig::new(kafka_uri))?;
 
         Ok(Arc::new(Self {
             consumer: None,
+            consumer_handle: None,
             producer: Arc::new(producer),
             receiver: std::marker::PhantomData,
             state,
@@ -72,9 +79,10 @@ where
         state: Arc<R::State>,
     ) -> Result<Self> {
         let consumer = KafkaConsumer::new(consumer_config)?;
-        let producer = KafkaProducer::create(&consumer_config.uri)?;
+        let producer = KafkaProducer::create(&ProducerConfig::new(&consumer_config.uri))?;
 
         let service = Self {
+            consumer_handle: Some(consumer.handle()),
             consumer: Some(consumer),
             producer: Arc::new(producer),
             receiver: std::marker::PhantomData,
@@ -109,16 +117,91 @@ where
         Ok(Arc::new(self))
     }
 
+    /// Starts the consumer in exactly-once pipeline mode: the records `T`
+    /// transforms every message into are produced through `producer`, in the
+    /// same transaction as the consumer offsets
+    pub async fn start_pipeline<T>(mut self, producer: TransactionalProducer) -> Result<Arc<Self>>
+    where
+        T: Transformer<State = R::State> + 'static,
+    {
+        if let Some(consumer) = self.consumer.take() {
+            let state = self.state.clone();
+
+            tokio::spawn(async move {
+                tracing::info!("🚀 Starting Kafka pipeline...");
+                if let Err(e) = consumer.consume_transform::<T>(&producer, state).await {
+                    tracing::error!("Kafka pipeline error: {}", e);
+                }
+                tracing::info!("🛑 Kafka pipeline stopped");
+            });
+            tracing::info!("✅ Kafka pipeline started successfully");
+        } else {
+            tracing::info!("ℹ️  No consumer to start (producer-only mode)");
+        }
+
+        Ok(Arc::new(self))
+    }
+
     pub async fn produce<M: KafkaModel>(&self, topic: &str, model: &M) -> Result<()> {
         self.producer.produce(topic, model).await
     }
 
+    pub async fn produce_with<M: KafkaModel>(
+        &self,
+        topic: &str,
+        model: &M,
+        options: &ProduceOptions,
+    ) -> Result<()> {
+        self.producer.produce_with(topic, model, options).await
+    }
+
     pub async fn produce_with_retry<M: KafkaModel>(&self, topic: &str, model: &M) -> Result<()> {
         self.producer
-            .produce_with_retries(topic, model, kafka_config().KAFKA_PRODUCE_RETRIES_COUNT)
+            .produce_with_retries(
+                topic,
+                model,
+                &RetryPolicy {
+                    max_retries: kafka_config().KAFKA_PRODUCE_RETRIES_COUNT,
+                    ..RetryPolicy::default()
+                },
+            )
             .await
     }
 
+    /// Controls the consumer (seek, pause/resume) after it was started
+    pub fn consumer_handle(&self) -> Option<&ConsumerHandle> {
+        self.consumer_handle.as_ref()
+    }
+
+    pub async fn produce_many<'m, M: KafkaModel + 'm>(
+        &self,
+        topic: &str,
+        models: impl IntoIterator<Item = &'m M> + Send,
+    ) -> Vec<Result<()>> {
+        self.producer.produce_many(topic, models).await
+    }
+
+    pub async fn produce_raw(
+        &self,
+        topic: &str,
+        key: impl Into<Vec<u8>> + Send,
+        payload: impl Into<Vec<u8>> + Send,
+    ) -> Result<()> {
+        self.producer.produce_raw(topic, key, payload).await
+    }
+
+    pub async fn produce_tombstone<K: Encode + Sync>(&self, topic: &str, key: &K) -> Result<()> {
+        self.producer.produce_tombstone(topic, key).await
+    }
+
+    /// Lag of every assigned partition, empty in producer-only mode
+    pub async fn consumer_lag(&self) -> Result<Vec<PartitionLag>> {
+        match &self.consumer_handle {
+            Some(handle) => handle.lag_async().await,
+            None => Ok(Vec::new()),
+        }
+    }
+
     pub fn producer(&self) -> &Arc<KafkaProducer> {
         &self.producer
     }